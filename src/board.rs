@@ -0,0 +1,307 @@
+use std::{fmt, str::FromStr};
+
+use cosmwasm_schema::{
+    cw_serde,
+    schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema},
+};
+use cosmwasm_std::StdError;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::ContractError;
+
+// a board cell, serialized as the "X", "O" and "-" strings games always used
+#[cw_serde]
+#[derive(Copy, Eq, Hash)]
+pub enum Cell {
+    #[serde(rename = "-")]
+    Empty,
+    #[serde(rename = "X")]
+    X,
+    #[serde(rename = "O")]
+    O,
+}
+
+impl Cell {
+    pub fn opponent(self) -> Cell {
+        match self {
+            Cell::X => Cell::O,
+            Cell::O => Cell::X,
+            Cell::Empty => Cell::Empty,
+        }
+    }
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cell::Empty => write!(f, "-"),
+            Cell::X => write!(f, "X"),
+            Cell::O => write!(f, "O"),
+        }
+    }
+}
+
+impl FromStr for Cell {
+    type Err = StdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "-" => Ok(Cell::Empty),
+            "X" => Ok(Cell::X),
+            "O" => Ok(Cell::O),
+            _ => Err(StdError::parse_err("Cell", format!("unknown cell {s}"))),
+        }
+    }
+}
+
+const WORD_BITS: usize = 16;
+
+// the eight lines of a 3x3 board, checked straight against the X and O masks
+const WIN_MASKS: [u16; 8] = [
+    0b000_000_111,
+    0b000_111_000,
+    0b111_000_000,
+    0b001_001_001,
+    0b010_010_010,
+    0b100_100_100,
+    0b100_010_001,
+    0b001_010_100,
+];
+
+// X and O cells as bitmasks of 16 cells per word, cell 0 being the lowest bit of the first word.
+// Dimensions live on the game
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Board {
+    cells: u16,
+    x: Vec<u16>,
+    o: Vec<u16>,
+}
+
+impl Board {
+    pub fn new(cells: usize) -> Self {
+        let words = cells.div_ceil(WORD_BITS);
+        Board {
+            cells: cells as u16,
+            x: vec![0; words],
+            o: vec![0; words],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells == 0
+    }
+
+    pub fn cell(&self, index: usize) -> Cell {
+        let (word, bit) = (index / WORD_BITS, 1 << (index % WORD_BITS));
+        if self.x[word] & bit != 0 {
+            Cell::X
+        } else if self.o[word] & bit != 0 {
+            Cell::O
+        } else {
+            Cell::Empty
+        }
+    }
+
+    pub fn cells(&self) -> Vec<Cell> {
+        (0..self.len()).map(|index| self.cell(index)).collect()
+    }
+
+    // board made of the given range of cells, like an ultimate sub-board
+    pub fn slice(&self, start: usize, len: usize) -> Board {
+        (start..start + len).map(|index| self.cell(index)).collect()
+    }
+
+    pub fn place(&mut self, index: usize, cell: Cell) -> Result<(), ContractError> {
+        if index >= self.len() {
+            return Err(ContractError::InvalidPosition {
+                position: (index + 1).to_string(),
+                max: self.cells,
+            });
+        }
+        if self.cell(index) != Cell::Empty {
+            return Err(ContractError::PositionTaken {});
+        }
+
+        let (word, bit) = (index / WORD_BITS, 1 << (index % WORD_BITS));
+        match cell {
+            Cell::X => self.x[word] |= bit,
+            Cell::O => self.o[word] |= bit,
+            Cell::Empty => {}
+        }
+        Ok(())
+    }
+
+    // indexes of the empty cells
+    pub fn legal_moves(&self) -> Vec<usize> {
+        (0..self.len())
+            .filter(|index| self.cell(*index) == Cell::Empty)
+            .collect()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.legal_moves().is_empty()
+    }
+
+    // checks if the same role fills win_length consecutive cells along a row, column or diagonal,
+    // within a layer or across layers, returns winning role or None if nobody won.
+    pub fn winner(
+        &self,
+        rows: usize,
+        cols: usize,
+        layers: usize,
+        win_length: usize,
+    ) -> Option<Cell> {
+        if (rows, cols, layers, win_length) == (3, 3, 1, 3) {
+            return WIN_MASKS.iter().find_map(|mask| {
+                if self.x[0] & mask == *mask {
+                    Some(Cell::X)
+                } else if self.o[0] & mask == *mask {
+                    Some(Cell::O)
+                } else {
+                    None
+                }
+            });
+        }
+
+        // (layer, row, col) steps for the 13 line directions of a cube, a flat board only uses the
+        // horizontal, vertical, negative diagonal and positive diagonal ones within its single layer
+        let directions: [(isize, isize, isize); 13] = [
+            (0, 0, 1),
+            (0, 1, 0),
+            (0, 1, 1),
+            (0, 1, -1),
+            (1, 0, 0),
+            (1, 0, 1),
+            (1, 0, -1),
+            (1, 1, 0),
+            (1, -1, 0),
+            (1, 1, 1),
+            (1, 1, -1),
+            (1, -1, 1),
+            (1, -1, -1),
+        ];
+        let (rows, cols, layers) = (rows as isize, cols as isize, layers as isize);
+        let cell = |layer: isize, row: isize, col: isize| {
+            self.cell((layer * rows * cols + row * cols + col) as usize)
+        };
+
+        for layer in 0..layers {
+            for row in 0..rows {
+                for col in 0..cols {
+                    let role = cell(layer, row, col);
+                    if role == Cell::Empty {
+                        continue;
+                    }
+
+                    for (layer_step, row_step, col_step) in directions {
+                        // skip lines that would run off the board
+                        let end =
+                            |start: isize, step: isize| start + step * (win_length as isize - 1);
+                        let end_layer = end(layer, layer_step);
+                        let end_row = end(row, row_step);
+                        let end_col = end(col, col_step);
+                        if end_layer >= layers
+                            || end_row < 0
+                            || end_row >= rows
+                            || end_col < 0
+                            || end_col >= cols
+                        {
+                            continue;
+                        }
+
+                        if (1..win_length as isize).all(|i| {
+                            cell(
+                                layer + layer_step * i,
+                                row + row_step * i,
+                                col + col_step * i,
+                            ) == role
+                        }) {
+                            return Some(role);
+                        }
+                    }
+                }
+            }
+        }
+
+        // returns None if there's no winner
+        None
+    }
+}
+
+impl FromIterator<Cell> for Board {
+    fn from_iter<T: IntoIterator<Item = Cell>>(cells: T) -> Self {
+        let cells: Vec<Cell> = cells.into_iter().collect();
+        let mut board = Board::new(cells.len());
+        for (index, cell) in cells.into_iter().enumerate() {
+            // every cell of a new board is empty and on the board
+            board.place(index, cell).unwrap();
+        }
+        board
+    }
+}
+
+// queries get the list of "X", "O" and "-" cells games always had, storage packs it through
+// the packed module
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.cells())
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<Cell>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+impl JsonSchema for Board {
+    fn schema_name() -> String {
+        "Board".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        <Vec<Cell>>::json_schema(gen)
+    }
+}
+
+// stored layout of a board, a single list of numbers with the cell count followed by the X words
+// and then the O words, so a 3x3 board is just [9, x, o]. Used with #[serde(with = "packed")]
+pub mod packed {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(board: &Board, serializer: S) -> Result<S::Ok, S::Error> {
+        let words = board.x.iter().chain(board.o.iter()).copied();
+        serializer.collect_seq([board.cells].into_iter().chain(words))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Board, D::Error> {
+        let packed = Vec::<u16>::deserialize(deserializer)?;
+
+        let invalid = || de::Error::custom("invalid packed board");
+        let (cells, words) = packed.split_first().ok_or_else(invalid)?;
+        let mut board = Board::new(*cells as usize);
+        if words.len() != board.x.len() * 2 {
+            return Err(invalid());
+        }
+        let (x, o) = words.split_at(board.x.len());
+        board.x = x.to_vec();
+        board.o = o.to_vec();
+
+        // every bit set must be a cell of the board taken by a single role
+        let unused_bits = (board.len()..board.x.len() * WORD_BITS).any(|index| {
+            let (word, bit) = (index / WORD_BITS, 1 << (index % WORD_BITS));
+            (board.x[word] | board.o[word]) & bit != 0
+        });
+        if unused_bits || board.x.iter().zip(&board.o).any(|(x, o)| x & o != 0) {
+            return Err(invalid());
+        }
+
+        Ok(board)
+    }
+}
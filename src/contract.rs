@@ -1,14 +1,76 @@
 use crate::{
-    msg::{JoinGameMsg, ResignMsg},
-    state::{CONFIG, GAME, STATE},
+    board::{Board, Cell},
+    msg::{
+        AcceptDrawMsg, AcceptOwnershipMsg, CancelGameMsg, ClaimTimeoutMsg, Coordinates,
+        DeclineDrawMsg, DeclineGameMsg, DrandConfig, EnterQueueMsg, GameResult, GameVariant,
+        JoinGameMsg, LeaderboardResponse, LeaveQueueMsg, LegacyGame, MigrateMsg, OfferDrawMsg,
+        PlayerStatsResponse, QueueEntry, QueueStatusResponse, RatingResponse, ReceiveMsg,
+        RenounceOwnershipMsg, ResignMsg, RevealSeedMsg, Seed, Stake, SubmitBeaconMsg,
+        SubmitUltimateMoveMsg, TransferOwnershipMsg, UpdateConfigMsg,
+    },
+    state::{
+        games, load_game, player_stats, queue, save_game, COMMITMENTS, CONFIG, LEGACY_CONFIG,
+        LEGACY_GAMES, LEGACY_MIGRATION, RATINGS, STATE,
+    },
     ContractError,
 };
+use cw2::{set_contract_version, CONTRACT};
+use drand_verify::{derive_randomness, G2PubkeyRfc, Pubkey};
+use ripemd::Ripemd160;
+use semver::Version;
 use sha2::{Digest, Sha256};
 
 use crate::msg::{
-    Config, CreateGameMsg, ExecuteMsg, Game, GameStatus, InstantiateMsg, State, SubmitMoveMsg,
+    Config, ConfigResponse, CreateGameMsg, ExecuteMsg, Game, GameResponse, GameStatus,
+    InstantiateMsg, ListGamesResponse, QueryMsg, State, StateResponse, SubmitMoveMsg,
 };
-use cosmwasm_std::{entry_point, Addr, DepsMut, Env, MessageInfo, Response};
+use cosmwasm_std::{
+    attr, entry_point, from_binary, to_binary, Addr, Api, Attribute, BankMsg, Binary,
+    CanonicalAddr, Coin, CosmosMsg, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response,
+    StdError, StdResult, Storage, Timestamp, Uint128, WasmMsg,
+};
+use cw20::{Cw20CoinVerified, Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw_storage_plus::Bound;
+use cw_utils::{nonpayable, Duration, Expiration};
+
+use crate::rating::{apply_delta, elo_delta};
+
+// one day to submit a move unless configured otherwise
+const DEFAULT_MOVE_TIMEOUT: Duration = Duration::Time(24 * 60 * 60);
+
+// new players start at 1200 and move at most 32 points per game unless configured otherwise
+const DEFAULT_INITIAL_RATING: u32 = 1200;
+const DEFAULT_K_FACTOR: u32 = 32;
+
+// open games expire after about a week of 6 second blocks unless configured otherwise
+const DEFAULT_OPEN_GAME_EXPIRY: u64 = 100_800;
+
+// timeouts and expiries are capped at 30 days, or as many 6 second blocks, so adding them to the
+// current block can't overflow
+const MAX_TIMEOUT_SECONDS: u64 = 30 * 24 * 60 * 60;
+const MAX_TIMEOUT_BLOCKS: u64 = MAX_TIMEOUT_SECONDS / 6;
+
+// boards go from classic 3x3 up to 19x19, win length can't exceed the longest side
+const MIN_BOARD_SIDE: u8 = 3;
+const MAX_BOARD_SIDE: u8 = 19;
+
+// version info for migration
+const CONTRACT_NAME: &str = "crates.io:tic-tac-toe";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// pagination defaults for list queries
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+// legacy games converted per migrate call, keeping each call's gas bounded
+const DEFAULT_MIGRATION_LIMIT: u32 = 100;
+const MAX_MIGRATION_LIMIT: u32 = 500;
+
+// waiting players looked at when entering the queue, past that the sender waits in the queue
+const MAX_QUEUE_CANDIDATES: usize = 30;
+
+// open games read per page of the open games query, expired ones included
+const MAX_OPEN_GAMES_READ: usize = 100;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -17,19 +79,203 @@ pub fn instantiate(
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
+    let open_game_expiry =
+        validate_expiry(msg.open_game_expiry.unwrap_or(DEFAULT_OPEN_GAME_EXPIRY))?;
+
+    let owner = deps
+        .api
+        .addr_validate(&msg.owner.unwrap_or(info.sender.to_string()))?;
     let config = Config {
-        owner: deps
-            .api
-            .addr_validate(&msg.owner.unwrap_or(info.sender.to_string()))?,
+        owner: Some(owner.clone()),
+        pending_owner: None,
+        move_timeout: validate_timeout(msg.move_timeout.unwrap_or(DEFAULT_MOVE_TIMEOUT))?,
+        open_game_expiry,
+        initial_rating: msg.initial_rating.unwrap_or(DEFAULT_INITIAL_RATING),
+        k_factor: msg.k_factor.unwrap_or(DEFAULT_K_FACTOR),
+        drand: msg.drand.map(validate_drand).transpose()?,
+        cw20_tokens: validate_tokens(deps.api, msg.cw20_tokens.unwrap_or_default())?,
     };
     CONFIG.save(deps.storage, &config)?;
 
     let state = State { latest_game_id: 0 };
     STATE.save(deps.storage, &state)?;
 
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     Ok(Response::new()
         .add_attribute("action", "instantiate")
-        .add_attribute("owner", config.owner))
+        .add_attribute("owner", owner))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    match CONTRACT.may_load(deps.storage)? {
+        // deployments from before versioning still use the original layout
+        None => migrate_legacy_config(deps.branch())?,
+        Some(previous) => {
+            let previous_version: Version = previous.version.parse().map_err(semver_error)?;
+            let version: Version = CONTRACT_VERSION.parse().map_err(semver_error)?;
+            if previous.contract != CONTRACT_NAME || previous_version > version {
+                return Err(ContractError::CannotMigrate {
+                    contract: previous.contract,
+                    version: previous.version,
+                });
+            }
+        }
+    };
+
+    // legacy games are converted in batches, carrying on from where the last migration stopped
+    let limit = msg
+        .limit
+        .unwrap_or(DEFAULT_MIGRATION_LIMIT)
+        .clamp(1, MAX_MIGRATION_LIMIT) as usize;
+    let migrated_games = migrate_legacy_games(deps.branch(), &env, limit)?;
+    let pending = LEGACY_MIGRATION.may_load(deps.storage)?.is_some();
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("version", CONTRACT_VERSION)
+        .add_attribute("migrated_games", migrated_games.to_string())
+        .add_attribute("legacy_games_pending", pending.to_string()))
+}
+
+fn semver_error(err: semver::Error) -> StdError {
+    StdError::generic_err(err.to_string())
+}
+
+// rewrites the config stored with the original layout and schedules the legacy games for
+// conversion. Player stats and ratings only count games completed after the migration
+fn migrate_legacy_config(deps: DepsMut) -> Result<(), ContractError> {
+    // settings that didn't exist yet take their defaults
+    let legacy_config = LEGACY_CONFIG.load(deps.storage)?;
+    let config = Config {
+        owner: Some(legacy_config.owner),
+        pending_owner: None,
+        move_timeout: DEFAULT_MOVE_TIMEOUT,
+        open_game_expiry: DEFAULT_OPEN_GAME_EXPIRY,
+        initial_rating: DEFAULT_INITIAL_RATING,
+        k_factor: DEFAULT_K_FACTOR,
+        drand: None,
+        cw20_tokens: vec![],
+    };
+    CONFIG.save(deps.storage, &config)?;
+
+    // every legacy game id comes before the next one to be created
+    let state = STATE.load(deps.storage)?;
+    LEGACY_MIGRATION.save(deps.storage, &(0, state.latest_game_id))?;
+
+    Ok(())
+}
+
+// converts up to limit games stored with the original layout, returns the number converted
+fn migrate_legacy_games(deps: DepsMut, env: &Env, limit: usize) -> Result<u64, ContractError> {
+    let (next_id, end_id) = match LEGACY_MIGRATION.may_load(deps.storage)? {
+        Some(range) => range,
+        None => return Ok(0),
+    };
+
+    // both layouts share a namespace, so the range stops before games created since
+    let config = CONFIG.load(deps.storage)?;
+    let legacy_games = LEGACY_GAMES
+        .range(
+            deps.storage,
+            Some(Bound::inclusive(next_id)),
+            Some(Bound::exclusive(end_id)),
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (id, legacy_game) in legacy_games.iter() {
+        // the raw record has to go first, the indexed map can't read it to clean up its indexes
+        LEGACY_GAMES.remove(deps.storage, *id);
+
+        let game = migrate_legacy_game(env, &config, legacy_game.clone())?;
+        save_game(deps.storage, *id, &game)?;
+    }
+
+    // a full batch may have more games after it, a short one means they're all converted
+    match legacy_games.last() {
+        Some((id, _)) if legacy_games.len() == limit && id + 1 < end_id => {
+            LEGACY_MIGRATION.save(deps.storage, &(id + 1, end_id))?
+        }
+        _ => LEGACY_MIGRATION.remove(deps.storage),
+    }
+
+    Ok(legacy_games.len() as u64)
+}
+
+fn migrate_legacy_game(env: &Env, config: &Config, legacy_game: LegacyGame) -> StdResult<Game> {
+    let LegacyGame {
+        id,
+        players,
+        status,
+        moves,
+        next_turn,
+        winner,
+    } = legacy_game;
+
+    // legacy games only recorded the winner. A win is only trusted with the winner's line on the
+    // board, the legacy line check misread some boards and resignations left no line at all
+    let moves = moves
+        .iter()
+        .map(|cell| cell.parse())
+        .collect::<StdResult<Board>>()?;
+    let line = moves.winner(3, 3, 1, 3);
+    let two_players = players.len() == 2 && players[0] != players[1];
+    let (status, result) = match (status, winner) {
+        // open games had no seed committed, so they can't be joined anymore
+        (GameStatus::Open, _) => (GameStatus::Cancelled, Some(GameResult::Cancelled)),
+        // legacy games let players join their own, those can't be finished
+        (status, _) if status != GameStatus::Completed && !two_players => {
+            (GameStatus::Cancelled, Some(GameResult::Cancelled))
+        }
+        (GameStatus::Completed, Some(winner)) => {
+            let role = match players.iter().position(|player| *player == winner) {
+                Some(0) => Cell::X,
+                Some(1) => Cell::O,
+                _ => Cell::Empty,
+            };
+            let result = match line {
+                Some(line) if line == role && role == Cell::X => GameResult::XWins,
+                Some(line) if line == role => GameResult::OWins,
+                _ => GameResult::Unverified { winner },
+            };
+            (GameStatus::Completed, Some(result))
+        }
+        (GameStatus::Completed, None) => (GameStatus::Completed, Some(GameResult::Draw)),
+        (status, _) => (status, None),
+    };
+
+    Ok(Game {
+        id,
+        players,
+        status,
+        moves,
+        rows: 3,
+        cols: 3,
+        layers: 1,
+        win_length: 3,
+        variant: GameVariant::Classic,
+        sub_boards: Board::default(),
+        next_sub_board: None,
+        next_turn,
+        result,
+        opponent: None,
+        stake: None,
+        // games in progress get a full move timeout from the migration on
+        move_timeout: config.move_timeout,
+        last_move_height: env.block.height,
+        last_move_time: env.block.time,
+        expiration: Expiration::AtHeight(env.block.height),
+        draw_offer: None,
+        seeds: vec![],
+        use_beacon: false,
+        beacon_round: None,
+        randomness: None,
+    })
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -43,70 +289,324 @@ pub fn execute(
         ExecuteMsg::CreateGame(data) => create_game(deps, env, info, data),
         ExecuteMsg::JoinGame(data) => join_game(deps, env, info, data),
         ExecuteMsg::SubmitMove(data) => submit_move(deps, env, info, data),
+        ExecuteMsg::SubmitUltimateMove(data) => submit_ultimate_move(deps, env, info, data),
         ExecuteMsg::Resign(data) => resign(deps, env, info, data),
+        ExecuteMsg::DeclineGame(data) => decline_game(deps, env, info, data),
+        ExecuteMsg::ClaimTimeout(data) => claim_timeout(deps, env, info, data),
+        ExecuteMsg::CancelGame(data) => cancel_game(deps, env, info, data),
+        ExecuteMsg::OfferDraw(data) => offer_draw(deps, env, info, data),
+        ExecuteMsg::AcceptDraw(data) => accept_draw(deps, env, info, data),
+        ExecuteMsg::DeclineDraw(data) => decline_draw(deps, env, info, data),
+        ExecuteMsg::EnterQueue(data) => enter_queue(deps, env, info, data),
+        ExecuteMsg::LeaveQueue(data) => leave_queue(deps, env, info, data),
+        ExecuteMsg::RevealSeed(data) => reveal_seed(deps, env, info, data),
+        ExecuteMsg::SubmitBeacon(data) => submit_beacon(deps, env, info, data),
+        ExecuteMsg::UpdateConfig(data) => update_config(deps, env, info, data),
+        ExecuteMsg::TransferOwnership(data) => transfer_ownership(deps, env, info, data),
+        ExecuteMsg::AcceptOwnership(data) => accept_ownership(deps, env, info, data),
+        ExecuteMsg::RenounceOwnership(data) => renounce_ownership(deps, env, info, data),
+        ExecuteMsg::Receive(data) => receive(deps, env, info, data),
+    }
+}
+
+pub fn receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    // anyone can call the hook directly with a made up sender, only trusted tokens are let in
+    let config = CONFIG.load(deps.storage)?;
+    if !config.cw20_tokens.contains(&info.sender) {
+        return Err(ContractError::TokenNotAccepted {
+            token: info.sender.to_string(),
+        });
+    }
+
+    // the cw20 contract calling this hook is info.sender, the player is the original sender
+    let player = deps.api.addr_validate(&wrapper.sender)?;
+    let stake = Stake::Cw20(Cw20CoinVerified {
+        address: info.sender,
+        amount: wrapper.amount,
+    });
+
+    match from_binary(&wrapper.msg)? {
+        ReceiveMsg::CreateGame(data) => {
+            create_game_with_stake(deps, env, player, data, Some(stake))
+        }
+        ReceiveMsg::JoinGame(data) => join_game_with_stake(deps, env, player, data, Some(stake)),
     }
 }
 
 pub fn create_game(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    _msg: CreateGameMsg,
+    msg: CreateGameMsg,
 ) -> Result<Response, ContractError> {
-    let state = STATE.load(deps.storage)?;
+    // funds sent along with the message are escrowed as the game stake
+    let stake = native_stake(&info.funds)?;
+    create_game_with_stake(deps, env, info.sender, msg, stake)
+}
 
+fn create_game_with_stake(
+    deps: DepsMut,
+    env: Env,
+    creator: Addr,
+    mut msg: CreateGameMsg,
+    stake: Option<Stake>,
+) -> Result<Response, ContractError> {
     // TO-DO: check all edge cases for failure
 
-    // set default values for an Open game and saves it to storage
-    let new_game = Game {
-        id: state.latest_game_id,
-        players: vec![info.sender],
-        status: GameStatus::Open,
-        moves: vec!["-".to_string(); 9],
-        next_turn: None,
-        winner: None,
-    };
-    GAME.save(deps.storage, state.latest_game_id, &new_game)?;
-
-    // increments latest_game_id and saves it to storage
-    STATE.save(
-        deps.storage,
-        &State {
-            latest_game_id: state.latest_game_id + 1,
-        },
-    )?;
+    msg.commitment = validate_commitment(deps.storage, &msg.commitment)?;
+    let new_game = new_game(deps, &env, creator, msg, stake)?;
 
-    Ok(Response::new()
+    let mut res = Response::new()
         .add_attribute("action", "create_game")
         .add_attribute("game_id", new_game.id.to_string())
-        .add_attribute("players", new_game.players[0].clone()))
+        .add_attribute("players", new_game.players[0].clone())
+        .add_attribute("variant", new_game.variant.to_string());
+
+    if let Some(opponent) = new_game.opponent {
+        res = res.add_attribute("opponent", opponent);
+    }
+
+    if let Some(stake) = new_game.stake {
+        res = res.add_attribute("stake", stake.to_string());
+    }
+
+    Ok(res)
 }
 
 pub fn join_game(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: JoinGameMsg,
 ) -> Result<Response, ContractError> {
-    let mut game = GAME.load(deps.storage, msg.game_id)?;
+    let stake = native_stake(&info.funds)?;
+    join_game_with_stake(deps, env, info.sender, msg, stake)
+}
+
+fn join_game_with_stake(
+    deps: DepsMut,
+    env: Env,
+    player: Addr,
+    msg: JoinGameMsg,
+    stake: Option<Stake>,
+) -> Result<Response, ContractError> {
+    let mut game = load_game(deps.storage, msg.game_id)?;
 
     if game.status != GameStatus::Open {
         return Err(ContractError::CantJoinGame {});
     }
 
+    if game.players.contains(&player) {
+        return Err(ContractError::CantJoinOwnGame {});
+    }
+
+    if game.expiration.is_expired(&env.block) {
+        return Err(ContractError::GameExpired {});
+    }
+
+    // private games can only be joined by the invited opponent
+    if game.opponent.is_some() && game.opponent != Some(player.clone()) {
+        return Err(ContractError::NotInvited {});
+    }
+
+    // second player must match the creator's stake exactly
+    if stake != game.stake {
+        return Err(ContractError::StakeMismatch {
+            expected: stake_to_string(&game.stake),
+            received: stake_to_string(&stake),
+        });
+    }
+
     // TO-DO: check all edge cases for failure
 
-    // add second player to players vector
-    game.players.push(info.sender.clone());
+    let commitment = validate_commitment(deps.storage, &msg.commitment)?;
+    let pubkey = validate_pubkey(deps.api, &player, msg.pubkey)?;
+    start_game(&env, &mut game, player, commitment.clone(), pubkey);
+
+    // the beacon must be one nobody could know when joining
+    if game.use_beacon {
+        let drand = CONFIG
+            .load(deps.storage)?
+            .drand
+            .ok_or(ContractError::BeaconNotConfigured {})?;
+        game.beacon_round = Some(next_round(&drand, env.block.time));
+    }
+
+    save_game(deps.storage, msg.game_id, &game)?;
+    COMMITMENTS.save(deps.storage, &commitment, &Empty {})?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "join_game")
+        .add_attribute("game_id", game.id.to_string())
+        .add_attribute("variant", game.variant.to_string());
+
+    if let Some(beacon_round) = game.beacon_round {
+        res = res.add_attribute("beacon_round", beacon_round.to_string());
+    }
+
+    Ok(res)
+}
+
+// sets default values for an Open game, saves it to storage and increments latest_game_id. The
+// caller validates the creator's commitment, which is recorded as used along with the game
+fn new_game(
+    deps: DepsMut,
+    env: &Env,
+    creator: Addr,
+    msg: CreateGameMsg,
+    stake: Option<Stake>,
+) -> Result<Game, ContractError> {
+    let state = STATE.load(deps.storage)?;
+
+    let commitment = msg.commitment;
+    let pubkey = validate_pubkey(deps.api, &creator, msg.pubkey)?;
+
+    // validate the invited opponent of a private game
+    let opponent = msg
+        .opponent
+        .map(|opponent| deps.api.addr_validate(&opponent))
+        .transpose()?;
+
+    // games fall back to the configured move timeout
+    let config = CONFIG.load(deps.storage)?;
+    let use_beacon = msg.use_beacon.unwrap_or(false);
+    if use_beacon && config.drand.is_none() {
+        return Err(ContractError::BeaconNotConfigured {});
+    }
+
+    let move_timeout = match msg.move_timeout {
+        Some(move_timeout) => validate_timeout(move_timeout)?,
+        None => config.move_timeout,
+    };
+
+    // ultimate games are played on nine classic sub-boards and qubic ones on a 4x4x4 cube,
+    // their size can't be changed
+    let variant = msg.variant.unwrap_or(GameVariant::Classic);
+    let custom_board = msg.rows.is_some() || msg.cols.is_some() || msg.win_length.is_some();
+    let fixed_board = matches!(variant, GameVariant::Ultimate | GameVariant::Qubic);
+
+    // classic 3x3 board with 3 in a row unless told otherwise
+    let side = if variant == GameVariant::Qubic { 4 } else { 3 };
+    let rows = msg.rows.unwrap_or(side);
+    let cols = msg.cols.unwrap_or(side);
+    let win_length = msg.win_length.unwrap_or(side);
+    let layers = if variant == GameVariant::Qubic { 4 } else { 1 };
+    if (fixed_board && custom_board)
+        || !(MIN_BOARD_SIDE..=MAX_BOARD_SIDE).contains(&rows)
+        || !(MIN_BOARD_SIDE..=MAX_BOARD_SIDE).contains(&cols)
+        || win_length < MIN_BOARD_SIDE
+        || win_length > rows.max(cols)
+    {
+        return Err(ContractError::InvalidBoard {
+            min: MIN_BOARD_SIDE,
+            max: MAX_BOARD_SIDE,
+        });
+    }
+
+    let game = Game {
+        id: state.latest_game_id,
+        players: vec![creator.clone()],
+        status: GameStatus::Open,
+        moves: match variant {
+            GameVariant::Ultimate => Board::new(81),
+            _ => Board::new(rows as usize * cols as usize * layers as usize),
+        },
+        rows,
+        cols,
+        layers,
+        win_length,
+        variant: variant.clone(),
+        sub_boards: match variant {
+            GameVariant::Ultimate => Board::new(9),
+            _ => Board::default(),
+        },
+        next_sub_board: None,
+        next_turn: None,
+        result: None,
+        opponent,
+        stake,
+        move_timeout,
+        last_move_height: env.block.height,
+        last_move_time: env.block.time,
+        expiration: Expiration::AtHeight(env.block.height + config.open_game_expiry),
+        draw_offer: None,
+        seeds: vec![Seed {
+            player: creator,
+            commitment: commitment.clone(),
+            secret: None,
+            pubkey,
+        }],
+        use_beacon,
+        beacon_round: None,
+        randomness: None,
+    };
+    save_game(deps.storage, state.latest_game_id, &game)?;
+    COMMITMENTS.save(deps.storage, &commitment, &Empty {})?;
+
+    STATE.save(
+        deps.storage,
+        &State {
+            latest_game_id: state.latest_game_id + 1,
+        },
+    )?;
+
+    Ok(game)
+}
+
+// adds the second player to an Open game and assigns roles, the caller saves the game
+fn start_game(
+    env: &Env,
+    game: &mut Game,
+    player: Addr,
+    commitment: String,
+    pubkey: Option<Binary>,
+) {
+    // add second player and their commitment
+    game.players.push(player.clone());
+    game.seeds.push(Seed {
+        player,
+        commitment,
+        secret: None,
+        pubkey,
+    });
+
+    // set game status to Revealing (from Open) and start the clock for both players to reveal
+    game.status = GameStatus::Revealing;
+    game.last_move_height = env.block.height;
+    game.last_move_time = env.block.time;
+}
+
+// decides who plays "X" once both seeds are revealed
+fn assign_roles(env: &Env, game: &mut Game) {
+    // calculate hash of the concatenated public keys followed by the concatenated secrets using
+    // Sha256. Neither player could predict it when committing to their secret
+    let hasher = game
+        .seeds
+        .iter()
+        .fold(Sha256::new(), |hasher, seed| match &seed.pubkey {
+            Some(pubkey) => hasher.chain_update(pubkey),
+            None => hasher,
+        });
+    let hasher = game.seeds.iter().fold(hasher, |hasher, seed| {
+        hasher.chain_update(seed.secret.as_deref().unwrap_or_default())
+    });
 
-    // calculate hash of concatenated strings using Sha256
-    let hash = Sha256::new()
-        .chain_update(game.players[0].to_string())
-        .chain_update(info.sender.to_string())
-        .finalize();
+    // followed by the beacon's randomness, which neither player can bias
+    let hash = match &game.randomness {
+        Some(randomness) => hasher.chain_update(randomness),
+        None => hasher,
+    }
+    .finalize();
 
     if hash[0].leading_zeros() != 0 {
-        // if there are no leading 0, it means first bit is 1. Game initiator plays "O" and goes last. Set next_turn to second player's address
+        // first bit is 0. Game initiator plays "O" and goes last. Set next_turn to second player's address
         game.next_turn = Some(game.players[1].clone());
 
         // re-aranging players vector to have second player on position 0
@@ -116,148 +616,1290 @@ pub fn join_game(
         game.next_turn = Some(game.players[0].clone())
     }
 
-    // set game status to InProgress (from Open) and save to storage
+    // set game status to InProgress (from Revealing) and start the first player's clock
     game.status = GameStatus::InProgress;
+    game.last_move_height = env.block.height;
+    game.last_move_time = env.block.time;
+}
+
+// checks the public key is a compressed secp256k1 key the player's address derives from, which
+// on cosmos chains is the ripemd160 of its sha256 hash
+fn validate_pubkey(
+    api: &dyn Api,
+    player: &Addr,
+    pubkey: Option<Binary>,
+) -> Result<Option<Binary>, ContractError> {
+    let Some(pubkey) = pubkey else {
+        return Ok(None);
+    };
 
-    GAME.save(deps.storage, msg.game_id, &game)?;
+    if pubkey.len() != 33 || !matches!(pubkey[0], 0x02 | 0x03) {
+        return Err(ContractError::InvalidPubkey {});
+    }
 
-    Ok(Response::new()
-        .add_attribute("action", "join_game")
-        .add_attribute("game_id", game.id.to_string())
-        .add_attribute("X", game.next_turn.unwrap()))
+    let derived = CanonicalAddr::from(Ripemd160::digest(Sha256::digest(&pubkey)).as_slice());
+    if api.addr_canonicalize(player.as_str())? != derived {
+        return Err(ContractError::PubkeyMismatch {});
+    }
+
+    Ok(Some(pubkey))
 }
 
-pub fn submit_move(
+// roles are assigned once every seed and the beacon, if any, are in
+fn ready_to_start(game: &Game) -> bool {
+    game.seeds.iter().all(|seed| seed.secret.is_some())
+        && (!game.use_beacon || game.randomness.is_some())
+}
+
+pub fn submit_beacon(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    msg: SubmitMoveMsg,
+    msg: SubmitBeaconMsg,
 ) -> Result<Response, ContractError> {
-    let mut game = GAME.load(deps.storage, msg.game_id)?;
+    nonpayable(&info)?;
 
-    if msg.position < 1 || msg.position > 9 {
-        return Err(ContractError::InvalidPosition {
-            position: msg.position.to_string(),
-        });
+    let mut game = load_game(deps.storage, msg.game_id)?;
+
+    if game.status != GameStatus::Revealing {
+        return Err(ContractError::GameNotRevealing {});
     }
 
-    if game.status != GameStatus::InProgress {
-        return Err(ContractError::GameNotInProgress {});
+    let beacon_round = match game.beacon_round {
+        Some(beacon_round) if game.randomness.is_none() => beacon_round,
+        _ => return Err(ContractError::BeaconNotRequested {}),
+    };
+
+    // any later round would let the submitter pick among several known outcomes
+    if msg.round != beacon_round {
+        return Err(ContractError::WrongBeaconRound {
+            expected: beacon_round,
+        });
     }
 
-    if game.moves[msg.position as usize - 1] != "-" {
-        return Err(ContractError::PositionTaken {});
+    let drand = CONFIG
+        .load(deps.storage)?
+        .drand
+        .ok_or(ContractError::BeaconNotConfigured {})?;
+    let pubkey = G2PubkeyRfc::from_variable(&drand.pubkey)
+        .map_err(|_| ContractError::InvalidDrandConfig {})?;
+    if !pubkey
+        .verify(msg.round, b"", &msg.signature)
+        .unwrap_or(false)
+    {
+        return Err(ContractError::InvalidBeacon {});
     }
 
-    if game.next_turn != Some(info.sender.clone()) {
-        return Err(ContractError::NotYourTurn {});
+    game.randomness = Some(Binary::from(derive_randomness(&msg.signature)));
+
+    let mut res = Response::new()
+        .add_attribute("action", "submit_beacon")
+        .add_attribute("game_id", game.id.to_string())
+        .add_attribute("round", msg.round.to_string());
+
+    // the game starts with the beacon if both seeds were already revealed
+    if ready_to_start(&game) {
+        assign_roles(&env, &mut game);
+        res = res.add_attribute("X", game.players[0].clone());
     }
 
-    // TO-DO: check all edge cases for failure
+    save_game(deps.storage, msg.game_id, &game)?;
 
-    // initialize role as String and assign X or O depending on game.players position
-    let role: String;
-    let opponent: Addr;
-    if game.players[0] == info.sender {
-        role = "X".to_string();
-        opponent = game.players[1].clone();
-    } else {
-        role = "O".to_string();
-        opponent = game.players[0].clone();
+    Ok(res)
+}
+
+// first drand round emitted strictly after the given time
+fn next_round(drand: &DrandConfig, time: Timestamp) -> u64 {
+    match time.seconds().checked_sub(drand.genesis_time) {
+        Some(elapsed) => elapsed / drand.period + 2,
+        None => 1,
     }
+}
 
-    // add player's decision in the correct position with their corresponding letter
-    game.moves[msg.position as usize - 1] = role.clone();
+fn validate_drand(drand: DrandConfig) -> Result<DrandConfig, ContractError> {
+    if drand.period == 0 || G2PubkeyRfc::from_variable(&drand.pubkey).is_err() {
+        return Err(ContractError::InvalidDrandConfig {});
+    }
+
+    Ok(drand)
+}
+
+// commitments are stored lowercase so they compare with the hex encoded hash of the secret. Each
+// one is only accepted once, otherwise a secret revealed in a game would be known in the next
+fn validate_commitment(storage: &dyn Storage, commitment: &str) -> Result<String, ContractError> {
+    if commitment.len() != 64 || !commitment.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ContractError::InvalidCommitment {});
+    }
 
-    // terminate the game if there're no more possible moves available
-    if !game.moves.contains(&"-".to_string()) {
-        game.status = GameStatus::Completed;
+    let commitment = commitment.to_ascii_lowercase();
+    if COMMITMENTS.has(storage, &commitment) {
+        return Err(ContractError::CommitmentUsed {});
     }
 
-    GAME.save(deps.storage, msg.game_id, &game)?;
+    Ok(commitment)
+}
+
+pub fn reveal_seed(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: RevealSeedMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
 
-    let winner = check_winner(game.moves.clone());
+    let mut game = load_game(deps.storage, msg.game_id)?;
 
-    // if there's a winner, set the game status to completed
-    if winner != None && winner != Some("-".to_string()) {
-        game.status = GameStatus::Completed;
+    if game.status != GameStatus::Revealing {
+        return Err(ContractError::GameNotRevealing {});
     }
 
-    // set winner to player's address
-    if winner == Some("O".to_string()) {
-        game.winner = Some(game.players[1].clone());
-    } else if winner != None && winner != Some("-".to_string()) {
-        game.winner = Some(game.players[0].clone());
+    let seed = game
+        .seeds
+        .iter_mut()
+        .find(|seed| seed.player == info.sender)
+        .ok_or(ContractError::Unauthorized {})?;
+
+    if seed.secret.is_some() {
+        return Err(ContractError::AlreadyRevealed {});
     }
 
-    // TO-DO: change next_turn address. Would be nice to track opponents address in a variable
-    game.next_turn = Some(opponent);
-    GAME.save(deps.storage, msg.game_id, &game)?;
+    if format!("{:x}", Sha256::digest(&msg.secret)) != seed.commitment {
+        return Err(ContractError::SeedMismatch {});
+    }
 
-    Ok(Response::new()
-        .add_attribute("action", "submit_move")
+    seed.secret = Some(msg.secret);
+
+    let mut res = Response::new()
+        .add_attribute("action", "reveal_seed")
         .add_attribute("game_id", game.id.to_string())
-        .add_attribute("position", msg.position.to_string())
-        .add_attribute("role", game.moves[msg.position as usize - 1].to_string()))
+        .add_attribute("player", info.sender);
+
+    // the game starts with the last reveal, unless still waiting for the beacon
+    if ready_to_start(&game) {
+        assign_roles(&env, &mut game);
+        res = res.add_attribute("X", game.players[0].clone());
+    }
+
+    save_game(deps.storage, msg.game_id, &game)?;
+
+    Ok(res)
 }
 
-fn check_winner(moves: Vec<String>) -> Option<String> {
-    // checks if same role is in the winning positions, returns winning role or None if nobody won.
+pub fn enter_queue(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: EnterQueueMsg,
+) -> Result<Response, ContractError> {
+    // matchmaking games are played without stakes
+    nonpayable(&info)?;
 
-    // win along horizontal?
-    for i in 0..3 {
-        if moves[0 + 3 * i] == moves[1 + 3 * i] && moves[0 + 3 * i] == moves[2 + 3 * i] {
-            return Some(moves[0 + 3 * i].clone());
-        }
+    if msg.min_rating > msg.max_rating {
+        return Err(ContractError::InvalidRatingRange {});
     }
 
-    // win along vertical?
-    for i in 0..3 {
-        if moves[0 + i] == moves[3 + i] && moves[0] == moves[6 + i] {
-            return Some(moves[0 + i].clone());
-        }
+    if queue().has(deps.storage, &info.sender) {
+        return Err(ContractError::AlreadyQueued {});
     }
 
-    // win along negative diagonal?
-    if moves[0] == moves[4] && moves[0] == moves[8] {
-        return Some(moves[4].clone());
-    }
+    let commitment = validate_commitment(deps.storage, &msg.commitment)?;
+    let pubkey = validate_pubkey(deps.api, &info.sender, msg.pubkey)?;
 
-    // win along positive diagonal?
-    if moves[2] == moves[4] && moves[2] == moves[6] {
-        return Some(moves[4].clone());
-    }
+    let rating = match RATINGS.may_load(deps.storage, &info.sender)? {
+        Some(rating) => rating,
+        None => CONFIG.load(deps.storage)?.initial_rating,
+    };
+
+    // look for a waiting player within the sender's range who also accepts the sender's rating.
+    // The empty address sorts before any other, so bounds cover every player with those ratings
+    let no_address = Addr::unchecked("");
+    let min = Bound::inclusive((msg.min_rating, &no_address));
+    let max = msg
+        .max_rating
+        .checked_add(1)
+        .map(|rating| Bound::exclusive((rating, &no_address)));
+    let opponent = queue()
+        .idx
+        .rating
+        .range(deps.storage, Some(min), max, Order::Ascending)
+        .take(MAX_QUEUE_CANDIDATES)
+        .map(|item| item.map(|(_, entry)| entry))
+        .find(|item| match item {
+            Ok(entry) => entry.min_rating <= rating && rating <= entry.max_rating,
+            Err(_) => true,
+        })
+        .transpose()?;
+
+    let opponent = match opponent {
+        Some(opponent) => opponent,
+        None => {
+            // nobody compatible is waiting, park the sender in the queue
+            let entry = QueueEntry {
+                player: info.sender.clone(),
+                commitment,
+                pubkey,
+                rating,
+                min_rating: msg.min_rating,
+                max_rating: msg.max_rating,
+            };
+            queue().save(deps.storage, &info.sender, &entry)?;
+            COMMITMENTS.save(deps.storage, &entry.commitment, &Empty {})?;
+
+            return Ok(Response::new()
+                .add_attribute("action", "enter_queue")
+                .add_attribute("player", info.sender)
+                .add_attribute("rating", rating.to_string()));
+        }
+    };
+
+    // the waiting player creates the game and the sender joins it right away
+    queue().remove(deps.storage, &opponent.player)?;
+
+    let msg = CreateGameMsg {
+        commitment: opponent.commitment,
+        pubkey: opponent.pubkey,
+        opponent: Some(info.sender.to_string()),
+        ..Default::default()
+    };
+    let mut game = new_game(deps.branch(), &env, opponent.player, msg, None)?;
+    COMMITMENTS.save(deps.storage, &commitment, &Empty {})?;
+    start_game(&env, &mut game, info.sender.clone(), commitment, pubkey);
+    save_game(deps.storage, game.id, &game)?;
 
-    // returns None if there's no winner
-    None
+    Ok(Response::new()
+        .add_attribute("action", "enter_queue")
+        .add_attribute("player", info.sender)
+        .add_attribute("rating", rating.to_string())
+        .add_attribute("game_id", game.id.to_string()))
 }
 
-pub fn resign(
+pub fn leave_queue(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    msg: ResignMsg,
+    _msg: LeaveQueueMsg,
 ) -> Result<Response, ContractError> {
-    let mut game = GAME.load(deps.storage, msg.game_id)?;
+    nonpayable(&info)?;
 
-    if game.status != GameStatus::InProgress {
-        return Err(ContractError::GameNotInProgress {});
+    if !queue().has(deps.storage, &info.sender) {
+        return Err(ContractError::NotQueued {});
     }
 
-    // set game status to Completed
-    game.status = GameStatus::Completed;
+    queue().remove(deps.storage, &info.sender)?;
 
-    // set winner to the opponent address and save to storage
-    if game.players[0] == info.sender {
-        game.winner = Some(game.players[1].clone());
-    } else {
-        game.winner = Some(game.players[0].clone());
+    Ok(Response::new()
+        .add_attribute("action", "leave_queue")
+        .add_attribute("player", info.sender))
+}
+
+pub fn decline_game(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: DeclineGameMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut game = load_game(deps.storage, msg.game_id)?;
+
+    if game.status != GameStatus::Open {
+        return Err(ContractError::GameNotOpen {});
+    }
+
+    // only the invited opponent of a private game can decline it
+    if game.opponent != Some(info.sender) {
+        return Err(ContractError::NotInvited {});
     }
-    GAME.save(deps.storage, msg.game_id, &game)?;
 
-    // TO-DO: handle unwrap safetly
+    // a declined invitation can never start, set game status to Cancelled and save to storage
+    game.status = GameStatus::Cancelled;
+    game.result = Some(GameResult::Cancelled);
+    save_game(deps.storage, msg.game_id, &game)?;
+
+    // return the creator's stake
     Ok(Response::new()
-        .add_attribute("action", "resign")
+        .add_messages(settle_stake(&game)?)
+        .add_attribute("action", "decline_game")
         .add_attribute("game_id", game.id.to_string())
-        .add_attribute("winner", game.winner.unwrap()))
+        .add_attribute("result", GameResult::Cancelled.to_string()))
+}
+
+pub fn cancel_game(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: CancelGameMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut game = load_game(deps.storage, msg.game_id)?;
+
+    if game.status != GameStatus::Open {
+        return Err(ContractError::GameNotOpen {});
+    }
+
+    // the creator can withdraw the game at any time, anyone can clean it up once expired
+    if game.players[0] != info.sender && !game.expiration.is_expired(&env.block) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // set game status to Cancelled and save to storage
+    game.status = GameStatus::Cancelled;
+    game.result = Some(GameResult::Cancelled);
+    save_game(deps.storage, msg.game_id, &game)?;
+
+    // return the creator's stake
+    Ok(Response::new()
+        .add_messages(settle_stake(&game)?)
+        .add_attribute("action", "cancel_game")
+        .add_attribute("game_id", game.id.to_string())
+        .add_attribute("result", GameResult::Cancelled.to_string()))
+}
+
+pub fn submit_move(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: SubmitMoveMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut game = load_game(deps.storage, msg.game_id)?;
+
+    if game.variant == GameVariant::Ultimate {
+        return Err(ContractError::WrongVariant {});
+    }
+
+    let position = move_position(&game, msg.position, msg.coordinates)?;
+
+    check_turn(&game, &info.sender)?;
+
+    // the position must be one of the board's empty cells
+    let index = position as usize - 1;
+    if !game.moves.legal_moves().contains(&index) {
+        return Err(ContractError::PositionTaken {});
+    }
+
+    let (role, rating_changes) = play_move(deps.storage, &env, &mut game, &info.sender, index)?;
+
+    let res = Response::new()
+        .add_attribute("action", "submit_move")
+        .add_attribute("game_id", game.id.to_string())
+        .add_attribute("position", position.to_string())
+        .add_attribute("role", role.to_string());
+
+    add_result(res, &game, rating_changes)
+}
+
+pub fn submit_ultimate_move(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: SubmitUltimateMoveMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut game = load_game(deps.storage, msg.game_id)?;
+
+    if game.variant != GameVariant::Ultimate {
+        return Err(ContractError::WrongVariant {});
+    }
+
+    // sub-boards and cells go from 1 to 9, being 1 top left and 9 bottom right
+    for position in [msg.sub_board, msg.cell] {
+        if !(1..=9).contains(&position) {
+            return Err(ContractError::InvalidPosition {
+                position: position.to_string(),
+                max: 9,
+            });
+        }
+    }
+
+    check_turn(&game, &info.sender)?;
+
+    // the previous move decides the sub-board, unless it sent the player to a closed one
+    match game.next_sub_board {
+        Some(expected) if expected != msg.sub_board => {
+            return Err(ContractError::WrongSubBoard { expected });
+        }
+        None if sub_board_closed(&game, msg.sub_board as usize - 1) => {
+            return Err(ContractError::SubBoardClosed {});
+        }
+        _ => {}
+    }
+
+    let index = (msg.sub_board as usize - 1) * 9 + msg.cell as usize - 1;
+    let (role, rating_changes) = play_move(deps.storage, &env, &mut game, &info.sender, index)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "submit_ultimate_move")
+        .add_attribute("game_id", game.id.to_string())
+        .add_attribute("sub_board", msg.sub_board.to_string())
+        .add_attribute("cell", msg.cell.to_string())
+        .add_attribute("role", role.to_string());
+
+    if let Some(next_sub_board) = game.next_sub_board {
+        res = res.add_attribute("next_sub_board", next_sub_board.to_string());
+    }
+
+    add_result(res, &game, rating_changes)
+}
+
+// validates the cell a move targets, returning its position on the board
+fn move_position(
+    game: &Game,
+    position: Option<u16>,
+    coordinates: Option<Coordinates>,
+) -> Result<u16, ContractError> {
+    let cells = game.moves.len() as u16;
+    match (position, coordinates) {
+        (Some(position), None) if (1..=cells).contains(&position) => Ok(position),
+        (Some(position), None) => Err(ContractError::InvalidPosition {
+            position: position.to_string(),
+            max: cells,
+        }),
+        (None, Some(Coordinates { x, y, z }))
+            if (1..=game.cols).contains(&x)
+                && (1..=game.rows).contains(&y)
+                && (1..=game.layers).contains(&z) =>
+        {
+            let (x, y, z) = (x as u16 - 1, y as u16 - 1, z as u16 - 1);
+            Ok(z * game.rows as u16 * game.cols as u16 + y * game.cols as u16 + x + 1)
+        }
+        (None, Some(Coordinates { x, y, z })) => Err(ContractError::InvalidPosition {
+            position: format!("({x}, {y}, {z})"),
+            max: cells,
+        }),
+        _ => Err(ContractError::InvalidMove {}),
+    }
+}
+
+// checks that the game is being played and it's the player's turn
+fn check_turn(game: &Game, player: &Addr) -> Result<(), ContractError> {
+    if game.status != GameStatus::InProgress {
+        return Err(ContractError::GameNotInProgress {});
+    }
+
+    check_players(game)?;
+
+    if game.next_turn.as_ref() != Some(player) {
+        return Err(ContractError::NotYourTurn {});
+    }
+
+    Ok(())
+}
+
+// places the player's role in the given cell of moves, completing the game if it was the last
+// move, and saves it to storage once. Returns the role played and any rating changes
+fn play_move(
+    storage: &mut dyn Storage,
+    env: &Env,
+    game: &mut Game,
+    player: &Addr,
+    index: usize,
+) -> Result<(Cell, Vec<Attribute>), ContractError> {
+    // TO-DO: check all edge cases for failure
+
+    // assign X or O depending on game.players position
+    let role: Cell;
+    let opponent: Addr;
+    if game.players[0] == *player {
+        role = Cell::X;
+        opponent = game.players[1].clone();
+    } else {
+        role = Cell::O;
+        opponent = game.players[0].clone();
+    }
+
+    // add player's decision in the correct position with their corresponding letter
+    game.moves.place(index, role)?;
+    game.last_move_height = env.block.height;
+    game.last_move_time = env.block.time;
+
+    // moving instead of answering withdraws the opponent's draw offer
+    if game.draw_offer == Some(opponent.clone()) {
+        game.draw_offer = None;
+    }
+
+    let winner = match game.variant {
+        GameVariant::Classic | GameVariant::Misere | GameVariant::Qubic => game.moves.winner(
+            game.rows as usize,
+            game.cols as usize,
+            game.layers as usize,
+            game.win_length as usize,
+        ),
+        GameVariant::Ultimate => check_ultimate_winner(game, index)?,
+    };
+
+    // in misère games whoever completes a line loses
+    let winner = match (&game.variant, winner) {
+        (GameVariant::Misere, Some(loser)) => Some(loser.opponent()),
+        (_, winner) => winner,
+    };
+
+    // if there's a winner, set the game status to completed with the winning role
+    let mut rating_changes = vec![];
+    if winner == Some(Cell::O) {
+        rating_changes = complete_game(storage, game, GameResult::OWins)?;
+    } else if winner == Some(Cell::X) {
+        rating_changes = complete_game(storage, game, GameResult::XWins)?;
+    } else if no_moves_left(game) {
+        // terminate the game if there're no more possible moves available
+        rating_changes = complete_game(storage, game, GameResult::Draw)?;
+    }
+
+    // TO-DO: change next_turn address. Would be nice to track opponents address in a variable
+    game.next_turn = Some(opponent);
+    save_game(storage, game.id, game)?;
+
+    Ok((role, rating_changes))
+}
+
+// adds the result, rating changes and stake payouts to a move's response once the game is over
+fn add_result(
+    res: Response,
+    game: &Game,
+    rating_changes: Vec<Attribute>,
+) -> Result<Response, ContractError> {
+    match &game.result {
+        Some(result) => Ok(res
+            .add_messages(settle_stake(game)?)
+            .add_attribute("result", result.to_string())
+            .add_attributes(rating_changes)),
+        None => Ok(res),
+    }
+}
+
+// records the winner of the sub-board the last move was played in, points the opponent to the
+// sub-board matching the cell played, and returns the role that won the overall board if any
+fn check_ultimate_winner(game: &mut Game, index: usize) -> Result<Option<Cell>, ContractError> {
+    let (sub_board, cell) = (index / 9, index % 9);
+
+    if let Some(role) = game.moves.slice(sub_board * 9, 9).winner(3, 3, 1, 3) {
+        game.sub_boards.place(sub_board, role)?;
+    }
+
+    // a won or full sub-board can't be played in, the opponent can then choose any open one
+    game.next_sub_board = match sub_board_closed(game, cell) {
+        true => None,
+        false => Some(cell as u8 + 1),
+    };
+
+    Ok(game.sub_boards.winner(3, 3, 1, 3))
+}
+
+// a sub-board is closed once won or full
+fn sub_board_closed(game: &Game, sub_board: usize) -> bool {
+    game.sub_boards.cell(sub_board) != Cell::Empty || game.moves.slice(sub_board * 9, 9).is_full()
+}
+
+fn no_moves_left(game: &Game) -> bool {
+    match game.variant {
+        GameVariant::Ultimate => (0..9).all(|sub_board| sub_board_closed(game, sub_board)),
+        _ => game.moves.is_full(),
+    }
+}
+
+// players are indexed as X and O once a game started, so there must be exactly two distinct ones
+fn check_players(game: &Game) -> Result<(), ContractError> {
+    if game.players.len() != 2 || game.players[0] == game.players[1] {
+        return Err(ContractError::InvalidPlayers {});
+    }
+
+    Ok(())
+}
+
+// sets a game as Completed with the given result, any pending draw offer is dropped, and
+// records the outcome in both players' stats and ratings. Returns the rating changes as attributes
+fn complete_game(
+    storage: &mut dyn Storage,
+    game: &mut Game,
+    result: GameResult,
+) -> Result<Vec<Attribute>, ContractError> {
+    check_players(game)?;
+
+    game.status = GameStatus::Completed;
+    game.result = Some(result.clone());
+    game.draw_offer = None;
+
+    let winner = game.winner();
+    for player in game.players.iter() {
+        let mut stats = player_stats()
+            .may_load(storage, player)?
+            .unwrap_or_default();
+
+        stats.games_played += 1;
+        match &winner {
+            Some(winner) if winner == player => stats.wins += 1,
+            Some(_) => stats.losses += 1,
+            None => stats.draws += 1,
+        }
+        if result == (GameResult::Resigned { by: player.clone() }) {
+            stats.resignations += 1;
+        }
+
+        player_stats().save(storage, player, &stats)?;
+    }
+
+    // rate "X" against "O", counting the score in half points
+    let config = CONFIG.load(storage)?;
+    let (x, o) = (&game.players[0], &game.players[1]);
+    let rating_x = RATINGS
+        .may_load(storage, x)?
+        .unwrap_or(config.initial_rating);
+    let rating_o = RATINGS
+        .may_load(storage, o)?
+        .unwrap_or(config.initial_rating);
+    let score_x = match &winner {
+        Some(winner) if winner == x => 2,
+        Some(_) => 0,
+        None => 1,
+    };
+
+    let delta = elo_delta(rating_x, rating_o, score_x, config.k_factor);
+    save_rating(storage, x, apply_delta(rating_x, delta))?;
+    save_rating(storage, o, apply_delta(rating_o, -delta))?;
+
+    Ok(vec![
+        attr("rating_delta", format!("{}:{:+}", x, delta)),
+        attr("rating_delta", format!("{}:{:+}", o, -delta)),
+    ])
+}
+
+// saves a player's new rating, along with their queue entry so matchmaking uses it right away
+fn save_rating(storage: &mut dyn Storage, player: &Addr, rating: u32) -> StdResult<()> {
+    RATINGS.save(storage, player, &rating)?;
+    if let Some(mut entry) = queue().may_load(storage, player)? {
+        entry.rating = rating;
+        queue().save(storage, player, &entry)?;
+    }
+    Ok(())
+}
+
+// native funds sent along with a message are escrowed as a single coin stake
+fn native_stake(funds: &[Coin]) -> Result<Option<Stake>, ContractError> {
+    match funds {
+        [] => Ok(None),
+        [coin] if !coin.amount.is_zero() => Ok(Some(Stake::Native(coin.clone()))),
+        _ => Err(ContractError::InvalidStake {}),
+    }
+}
+
+// builds the messages releasing a game's escrowed stake: the winner takes every stake,
+// otherwise each player gets their own stake back
+fn settle_stake(game: &Game) -> StdResult<Vec<CosmosMsg>> {
+    let stake = match &game.stake {
+        Some(stake) => stake,
+        None => return Ok(vec![]),
+    };
+
+    match &game.winner() {
+        Some(winner) => {
+            let pot = stake.amount() * Uint128::from(game.players.len() as u128);
+            Ok(vec![transfer_stake(stake, winner, pot)?])
+        }
+        None => game
+            .players
+            .iter()
+            .map(|player| transfer_stake(stake, player, stake.amount()))
+            .collect(),
+    }
+}
+
+fn transfer_stake(stake: &Stake, recipient: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+    let msg = match stake {
+        Stake::Native(coin) => BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin {
+                denom: coin.denom.clone(),
+                amount,
+            }],
+        }
+        .into(),
+        Stake::Cw20(cw20) => WasmMsg::Execute {
+            contract_addr: cw20.address.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }
+        .into(),
+    };
+    Ok(msg)
+}
+
+fn stake_to_string(stake: &Option<Stake>) -> String {
+    match stake {
+        Some(stake) => stake.to_string(),
+        None => "nothing".to_string(),
+    }
+}
+
+pub fn resign(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: ResignMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut game = load_game(deps.storage, msg.game_id)?;
+
+    if game.status != GameStatus::InProgress {
+        return Err(ContractError::GameNotInProgress {});
+    }
+
+    // only the players themselves can resign
+    if !game.players.contains(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // set game status to Completed, recording who resigned so the opponent wins, and save to storage
+    let result = GameResult::Resigned {
+        by: info.sender.clone(),
+    };
+    let rating_changes = complete_game(deps.storage, &mut game, result.clone())?;
+    let winner = game.winner().ok_or(ContractError::InvalidPlayers {})?;
+    save_game(deps.storage, msg.game_id, &game)?;
+
+    Ok(Response::new()
+        .add_messages(settle_stake(&game)?)
+        .add_attribute("action", "resign")
+        .add_attribute("game_id", game.id.to_string())
+        .add_attribute("winner", winner)
+        .add_attribute("result", result.to_string())
+        .add_attributes(rating_changes))
+}
+
+pub fn offer_draw(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: OfferDrawMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut game = load_game(deps.storage, msg.game_id)?;
+
+    if game.status != GameStatus::InProgress {
+        return Err(ContractError::GameNotInProgress {});
+    }
+
+    if !game.players.contains(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if game.draw_offer.is_some() {
+        return Err(ContractError::DrawAlreadyOffered {});
+    }
+
+    // record the offer until the opponent answers it or moves
+    game.draw_offer = Some(info.sender.clone());
+    save_game(deps.storage, msg.game_id, &game)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "offer_draw")
+        .add_attribute("game_id", game.id.to_string())
+        .add_attribute("offered_by", info.sender))
+}
+
+pub fn accept_draw(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: AcceptDrawMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut game = load_game(deps.storage, msg.game_id)?;
+
+    check_draw_offer(&game, &info.sender)?;
+
+    // complete the game as a draw, same as a full board, and save to storage
+    let rating_changes = complete_game(deps.storage, &mut game, GameResult::Draw)?;
+    save_game(deps.storage, msg.game_id, &game)?;
+
+    Ok(Response::new()
+        .add_messages(settle_stake(&game)?)
+        .add_attribute("action", "accept_draw")
+        .add_attribute("game_id", game.id.to_string())
+        .add_attribute("result", GameResult::Draw.to_string())
+        .add_attributes(rating_changes))
+}
+
+pub fn decline_draw(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: DeclineDrawMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut game = load_game(deps.storage, msg.game_id)?;
+
+    check_draw_offer(&game, &info.sender)?;
+
+    // withdraw the offer, the game goes on
+    game.draw_offer = None;
+    save_game(deps.storage, msg.game_id, &game)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "decline_draw")
+        .add_attribute("game_id", game.id.to_string()))
+}
+
+// only the opponent of the player who offered a draw can answer it
+fn check_draw_offer(game: &Game, sender: &Addr) -> Result<(), ContractError> {
+    if game.status != GameStatus::InProgress {
+        return Err(ContractError::GameNotInProgress {});
+    }
+
+    if !game.players.contains(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match &game.draw_offer {
+        Some(offered_by) if offered_by != sender => Ok(()),
+        _ => Err(ContractError::NoDrawOffer {}),
+    }
+}
+
+pub fn claim_timeout(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ClaimTimeoutMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut game = load_game(deps.storage, msg.game_id)?;
+
+    if game.status == GameStatus::Revealing {
+        return claim_reveal_timeout(deps, env, info, game);
+    }
+
+    if game.status != GameStatus::InProgress {
+        return Err(ContractError::GameNotInProgress {});
+    }
+
+    // only the player waiting for the opponent's move can claim the win
+    if !game.players.contains(&info.sender) || game.next_turn == Some(info.sender.clone()) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if !move_expiration(&game).is_expired(&env.block) {
+        return Err(ContractError::TimeoutNotExpired {});
+    }
+
+    // set game status to Completed, next_turn timed out so the waiting player wins
+    let result = GameResult::TimedOut {
+        by: game.next_turn.clone().unwrap(),
+    };
+    let rating_changes = complete_game(deps.storage, &mut game, result.clone())?;
+    save_game(deps.storage, msg.game_id, &game)?;
+
+    Ok(Response::new()
+        .add_messages(settle_stake(&game)?)
+        .add_attribute("action", "claim_timeout")
+        .add_attribute("game_id", game.id.to_string())
+        .add_attribute("winner", info.sender)
+        .add_attribute("result", result.to_string())
+        .add_attributes(rating_changes))
+}
+
+// a player who revealed wins against one who refuses to, the game is cancelled and stakes are
+// refunded if neither did or only the beacon is missing
+fn claim_reveal_timeout(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mut game: Game,
+) -> Result<Response, ContractError> {
+    let (revealed, pending): (Vec<Seed>, Vec<Seed>) = game
+        .seeds
+        .iter()
+        .cloned()
+        .partition(|seed| seed.secret.is_some());
+
+    // the claimer can't be the one holding the game back
+    if !game.players.contains(&info.sender)
+        || pending
+            .iter()
+            .any(|seed| seed.player == info.sender && !revealed.is_empty())
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if !move_expiration(&game).is_expired(&env.block) {
+        return Err(ContractError::TimeoutNotExpired {});
+    }
+
+    // nobody is to blame when both revealed but the beacon never came
+    let (result, rating_changes) = match (revealed.is_empty(), pending.first()) {
+        (false, Some(pending)) => {
+            let result = GameResult::TimedOut {
+                by: pending.player.clone(),
+            };
+            let rating_changes = complete_game(deps.storage, &mut game, result.clone())?;
+            (result, rating_changes)
+        }
+        _ => {
+            game.status = GameStatus::Cancelled;
+            game.result = Some(GameResult::Cancelled);
+            (GameResult::Cancelled, vec![])
+        }
+    };
+    save_game(deps.storage, game.id, &game)?;
+
+    let mut res = Response::new()
+        .add_messages(settle_stake(&game)?)
+        .add_attribute("action", "claim_timeout")
+        .add_attribute("game_id", game.id.to_string());
+
+    if let Some(winner) = game.winner() {
+        res = res.add_attribute("winner", winner);
+    }
+
+    Ok(res
+        .add_attribute("result", result.to_string())
+        .add_attributes(rating_changes))
+}
+
+// point in time at which next_turn loses the right to move
+fn move_expiration(game: &Game) -> Expiration {
+    match game.move_timeout {
+        Duration::Height(blocks) => Expiration::AtHeight(game.last_move_height + blocks),
+        Duration::Time(seconds) => Expiration::AtTime(game.last_move_time.plus_seconds(seconds)),
+    }
+}
+
+// checks that the sender is the contract owner
+fn check_owner(config: &Config, sender: &Addr) -> Result<(), ContractError> {
+    if config.owner.as_ref() != Some(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
+}
+
+pub fn update_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: UpdateConfigMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    check_owner(&config, &info.sender)?;
+
+    // changes only apply to games created from now on, except for rating settings
+    if let Some(move_timeout) = msg.move_timeout {
+        config.move_timeout = validate_timeout(move_timeout)?;
+    }
+    if let Some(open_game_expiry) = msg.open_game_expiry {
+        config.open_game_expiry = validate_expiry(open_game_expiry)?;
+    }
+    if let Some(initial_rating) = msg.initial_rating {
+        config.initial_rating = initial_rating;
+    }
+    if let Some(k_factor) = msg.k_factor {
+        config.k_factor = k_factor;
+    }
+    if let Some(drand) = msg.drand {
+        config.drand = Some(validate_drand(drand)?);
+    }
+    if let Some(cw20_tokens) = msg.cw20_tokens {
+        config.cw20_tokens = validate_tokens(deps.api, cw20_tokens)?;
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "update_config"))
+}
+
+pub fn transfer_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: TransferOwnershipMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    check_owner(&config, &info.sender)?;
+
+    // the current owner stays in charge until the new one accepts
+    let pending_owner = deps.api.addr_validate(&msg.new_owner)?;
+    config.pending_owner = Some(pending_owner.clone());
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "transfer_ownership")
+        .add_attribute("pending_owner", pending_owner))
+}
+
+pub fn accept_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    _msg: AcceptOwnershipMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+
+    if config.pending_owner.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.owner = config.pending_owner.take();
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("owner", info.sender))
+}
+
+pub fn renounce_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    _msg: RenounceOwnershipMsg,
+) -> Result<Response, ContractError> {
+    nonpayable(&info)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    check_owner(&config, &info.sender)?;
+
+    // a pending transfer would otherwise bring an owner back
+    config.owner = None;
+    config.pending_owner = None;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "renounce_ownership"))
+}
+
+fn validate_tokens(api: &dyn Api, tokens: Vec<String>) -> StdResult<Vec<Addr>> {
+    tokens
+        .iter()
+        .map(|token| api.addr_validate(token))
+        .collect()
+}
+
+fn validate_timeout(move_timeout: Duration) -> Result<Duration, ContractError> {
+    match move_timeout {
+        Duration::Height(0) | Duration::Time(0) => Err(ContractError::InvalidTimeout {}),
+        Duration::Height(blocks) if blocks > MAX_TIMEOUT_BLOCKS => {
+            Err(ContractError::TimeoutTooLong {})
+        }
+        Duration::Time(seconds) if seconds > MAX_TIMEOUT_SECONDS => {
+            Err(ContractError::TimeoutTooLong {})
+        }
+        _ => Ok(move_timeout),
+    }
+}
+
+// open game expiries are counted in blocks
+fn validate_expiry(blocks: u64) -> Result<u64, ContractError> {
+    match blocks {
+        0 => Err(ContractError::InvalidTimeout {}),
+        blocks if blocks > MAX_TIMEOUT_BLOCKS => Err(ContractError::TimeoutTooLong {}),
+        _ => Ok(blocks),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Game { game_id } => to_binary(&query_game(deps, game_id)?),
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::State {} => to_binary(&query_state(deps)?),
+        QueryMsg::ListGames {
+            status,
+            start_after,
+            limit,
+        } => to_binary(&query_list_games(deps, status, start_after, limit)?),
+        QueryMsg::GamesByPlayer {
+            player,
+            start_after,
+            limit,
+        } => to_binary(&query_games_by_player(deps, player, start_after, limit)?),
+        QueryMsg::PlayerStats { address } => to_binary(&query_player_stats(deps, address)?),
+        QueryMsg::Rating { address } => to_binary(&query_rating(deps, address)?),
+        QueryMsg::QueueStatus { address } => to_binary(&query_queue_status(deps, address)?),
+        QueryMsg::Leaderboard { start_after, limit } => {
+            to_binary(&query_leaderboard(deps, start_after, limit)?)
+        }
+        QueryMsg::OpenGames { start_after, limit } => {
+            to_binary(&query_open_games(deps, env, start_after, limit)?)
+        }
+    }
+}
+
+pub fn query_game(deps: Deps, game_id: u64) -> StdResult<GameResponse> {
+    let game = load_game(deps.storage, game_id)?;
+    Ok(GameResponse { game })
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        owner: config.owner,
+        pending_owner: config.pending_owner,
+        move_timeout: config.move_timeout,
+        open_game_expiry: config.open_game_expiry,
+        initial_rating: config.initial_rating,
+        k_factor: config.k_factor,
+        drand: config.drand,
+        cw20_tokens: config.cw20_tokens,
+    })
+}
+
+pub fn query_state(deps: Deps) -> StdResult<StateResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(StateResponse {
+        latest_game_id: state.latest_game_id,
+    })
+}
+
+pub fn query_list_games(
+    deps: Deps,
+    status: Option<GameStatus>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ListGamesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    // use the status index when filtering, otherwise walk every game in ascending id order
+    let games = match status {
+        Some(status) => games()
+            .idx
+            .status
+            .prefix(status.to_string())
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, game)| game.into()))
+            .collect::<StdResult<Vec<_>>>()?,
+        None => games()
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| item.map(|(_, game)| game.into()))
+            .collect::<StdResult<Vec<_>>>()?,
+    };
+
+    Ok(ListGamesResponse { games })
+}
+
+pub fn query_open_games(
+    deps: Deps,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ListGamesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    // expired games keep their Open status until cancelled, but can't be joined anymore. Reads are
+    // capped so piled up expired games can't exhaust the query's gas, anyone can cancel them
+    let games = games()
+        .idx
+        .status
+        .prefix(GameStatus::Open.to_string())
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(MAX_OPEN_GAMES_READ)
+        .filter(|item| match item {
+            Ok((_, game)) => !game.expiration.is_expired(&env.block),
+            Err(_) => true,
+        })
+        .take(limit)
+        .map(|item| item.map(|(_, game)| game.into()))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ListGamesResponse { games })
+}
+
+pub fn query_games_by_player(
+    deps: Deps,
+    player: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ListGamesResponse> {
+    let player = deps.api.addr_validate(&player)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    // the player index only holds game ids, load each game from the primary map
+    let games = games()
+        .idx
+        .player
+        .idx
+        .prefix(player)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|game_id| load_game(deps.storage, game_id?))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ListGamesResponse { games })
+}
+
+pub fn query_player_stats(deps: Deps, address: String) -> StdResult<PlayerStatsResponse> {
+    let address = deps.api.addr_validate(&address)?;
+
+    // players that never finished a game have empty stats
+    let stats = player_stats()
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+
+    Ok(PlayerStatsResponse { address, stats })
+}
+
+pub fn query_rating(deps: Deps, address: String) -> StdResult<RatingResponse> {
+    let address = deps.api.addr_validate(&address)?;
+
+    // unrated players start at the configured initial rating
+    let rating = match RATINGS.may_load(deps.storage, &address)? {
+        Some(rating) => rating,
+        None => CONFIG.load(deps.storage)?.initial_rating,
+    };
+
+    Ok(RatingResponse { address, rating })
+}
+
+pub fn query_queue_status(deps: Deps, address: String) -> StdResult<QueueStatusResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let entry = queue().may_load(deps.storage, &address)?;
+    Ok(QueueStatusResponse { entry })
+}
+
+pub fn query_leaderboard(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<LeaderboardResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    // resume right after the given player's position, which depends on their current wins. A
+    // player without stats has no position, so the leaderboard starts over from the top
+    let start_after = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    let max = match &start_after {
+        Some(address) => player_stats()
+            .may_load(deps.storage, address)?
+            .map(|stats| Bound::exclusive((stats.wins, address))),
+        None => None,
+    };
+
+    // walk the wins index from the most wins down
+    let players = player_stats()
+        .idx
+        .wins
+        .range(deps.storage, None, max, Order::Descending)
+        .take(limit)
+        .map(|item| item.map(|(address, stats)| PlayerStatsResponse { address, stats }))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(LeaderboardResponse { players })
 }
@@ -1,5 +1,6 @@
 use crate::ContractError::Unauthorized;
 use cosmwasm_std::StdError;
+use cw_utils::PaymentError;
 use serde_json::Error;
 use thiserror::Error;
 
@@ -8,6 +9,12 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
+    #[error("Cannot migrate from {contract} {version}")]
+    CannotMigrate { contract: String, version: String },
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -20,14 +27,114 @@ pub enum ContractError {
     #[error("Game not in progress")]
     GameNotInProgress {},
 
-    #[error("Cannot join a game that is InProgress or Completed")]
+    #[error("Cannot join a game that is Revealing, InProgress, Completed or Cancelled")]
     CantJoinGame {},
 
+    #[error("Cannot join your own game")]
+    CantJoinOwnGame {},
+
+    #[error("Game must have exactly two distinct players")]
+    InvalidPlayers {},
+
+    #[error("Game expired before anyone joined")]
+    GameExpired {},
+
+    #[error("Only the invited opponent can join or decline this game")]
+    NotInvited {},
+
+    #[error("Game is not open")]
+    GameNotOpen {},
+
+    #[error("A stake must be a single non-zero coin")]
+    InvalidStake {},
+
+    #[error("Stake mismatch: expected {expected}, received {received}")]
+    StakeMismatch { expected: String, received: String },
+
+    #[error("Timeouts and expiries must be greater than zero")]
+    InvalidTimeout {},
+
+    #[error("Timeouts and expiries can't exceed 30 days")]
+    TimeoutTooLong {},
+
+    #[error("Opponent's move timeout has not expired yet")]
+    TimeoutNotExpired {},
+
+    #[error("A draw offer is already pending")]
+    DrawAlreadyOffered {},
+
+    #[error("No draw offer from your opponent to answer")]
+    NoDrawOffer {},
+
+    #[error("Minimum rating can't be greater than maximum rating")]
+    InvalidRatingRange {},
+
+    #[error("Already waiting in the queue")]
+    AlreadyQueued {},
+
+    #[error("Not waiting in the queue")]
+    NotQueued {},
+
     #[error(
-        "Position must be an integer between 1 and 9 (inclusive). Your choice was {}",
+        "Position must be an integer between 1 and {} (inclusive). Your choice was {}",
+        max,
         position
     )]
-    InvalidPosition { position: String },
+    InvalidPosition { position: String, max: u16 },
+
+    #[error("Commitment must be a hex encoded sha256 hash")]
+    InvalidCommitment {},
+
+    #[error("Commitment was already used, commit to a new secret")]
+    CommitmentUsed {},
+
+    #[error("Public key must be a compressed secp256k1 key")]
+    InvalidPubkey {},
+
+    #[error("Public key doesn't belong to the sender")]
+    PubkeyMismatch {},
+
+    #[error("Drand beacons are not configured")]
+    BeaconNotConfigured {},
+
+    #[error("Invalid drand configuration")]
+    InvalidDrandConfig {},
+
+    #[error("Game is not waiting for a beacon")]
+    BeaconNotRequested {},
+
+    #[error("Cw20 token {token} isn't accepted for stakes")]
+    TokenNotAccepted { token: String },
+
+    #[error("Beacon must be from round {expected}")]
+    WrongBeaconRound { expected: u64 },
+
+    #[error("Beacon signature doesn't verify")]
+    InvalidBeacon {},
+
+    #[error("Game is not waiting for seeds to be revealed")]
+    GameNotRevealing {},
+
+    #[error("Seed already revealed")]
+    AlreadyRevealed {},
+
+    #[error("Secret doesn't match the commitment")]
+    SeedMismatch {},
+
+    #[error("Move needs either a position or coordinates")]
+    InvalidMove {},
+
+    #[error("Move doesn't match the game variant")]
+    WrongVariant {},
+
+    #[error("Move must be played in sub-board {expected}")]
+    WrongSubBoard { expected: u8 },
+
+    #[error("Sub-board is already won or full")]
+    SubBoardClosed {},
+
+    #[error("Board sides must be between {min} and {max}, win length between {min} and the longest side. Ultimate and Qubic boards can't be resized")]
+    InvalidBoard { min: u8, max: u8 },
 
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
@@ -1,6 +1,8 @@
+pub mod board;
 pub mod contract;
 mod error;
-mod msg;
+pub mod msg;
+mod rating;
 pub mod state;
 pub use crate::error::ContractError;
 #[cfg(test)]
@@ -1,10 +1,37 @@
-use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Addr;
+use crate::board::Board;
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Binary, Coin, Timestamp, Uint128};
+use cw20::{Cw20CoinVerified, Cw20ReceiveMsg};
+use cw_utils::{Duration, Expiration};
+use std::fmt;
 
 #[cw_serde]
 pub struct Config {
-    // smart contract owner
-    pub owner: Addr,
+    // smart contract owner, None once ownership is renounced
+    pub owner: Option<Addr>,
+    // address the owner proposed to hand the contract over to, until it accepts
+    pub pending_owner: Option<Addr>,
+    // default time a player has to submit a move before the opponent can claim the win
+    pub move_timeout: Duration,
+    // number of blocks an open game can wait for an opponent before it expires
+    pub open_game_expiry: u64,
+    // Elo rating given to players before their first completed game
+    pub initial_rating: u32,
+    // maximum Elo rating change per game
+    pub k_factor: u32,
+    // drand network verifying beacons of games that wait for one, None disables them
+    pub drand: Option<DrandConfig>,
+    // cw20 contracts whose tokens can be staked, any other Receive hook caller is rejected
+    #[serde(default)]
+    pub cw20_tokens: Vec<Addr>,
+}
+
+// a drand network using the bls-unchained-g1-rfc9380 scheme, like quicknet
+#[cw_serde]
+pub struct DrandConfig {
+    pub pubkey: Binary,    // 96 bytes G2 public key of the network
+    pub genesis_time: u64, // unix time in seconds of round 1
+    pub period: u64,       // seconds between rounds
 }
 
 #[cw_serde]
@@ -15,23 +42,209 @@ pub struct State {
 #[cw_serde]
 pub enum GameStatus {
     Open,
+    Revealing, // both players joined, waiting for their seeds to assign roles
     InProgress,
     Completed,
+    Cancelled,
+}
+
+impl fmt::Display for GameStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameStatus::Open => write!(f, "open"),
+            GameStatus::Revealing => write!(f, "revealing"),
+            GameStatus::InProgress => write!(f, "in_progress"),
+            GameStatus::Completed => write!(f, "completed"),
+            GameStatus::Cancelled => write!(f, "cancelled"),
+        }
+    }
 }
 
 #[cw_serde]
 pub struct Game {
-    pub id: u64,                 // game id to be able to handle multiple games at once
+    pub id: u64,                    // game id to be able to handle multiple games at once
     pub players: Vec<Addr>, // vector of players' addresses, index 0 contains the address of player "X" who goes first
     pub status: GameStatus, // track game status
-    pub moves: Vec<String>, // vector that contains both player's moves in 1-9 board (index 0-8)
-    pub next_turn: Option<Addr>, // tracks who plays next
-    pub winner: Option<Addr>, // None as long as the game is Open or in Progress, contains the address of the winner once game is Completed
+    pub moves: Board, // vector that contains both player's moves row by row and layer by layer, position 1 is index 0
+    pub rows: u8,     // board height
+    pub cols: u8,     // board width
+    pub layers: u8,   // board depth, 1 unless the board is a cube
+    pub win_length: u8, // number of cells in a row, column or diagonal needed to win
+    pub variant: GameVariant, // rules the game is played with
+    pub sub_boards: Board, // Ultimate only, role that won each sub-board or Empty while undecided
+    pub next_sub_board: Option<u8>, // Ultimate only, sub-board (1-9) next_turn must play in, None if any open one
+    pub next_turn: Option<Addr>,    // tracks who plays next
+    pub result: Option<GameResult>, // None as long as the game is Open or in Progress, set once the game is Completed or Cancelled
+    pub opponent: Option<Addr>,     // invited opponent of a private game, None if anyone can join
+    pub stake: Option<Stake>, // tokens each player escrows to play, the winner takes both stakes
+    pub move_timeout: Duration, // time next_turn has to move before the opponent can claim the win
+    pub last_move_height: u64, // block height of the last move, or of game creation/start if nobody moved yet
+    pub last_move_time: Timestamp, // block time of the last move, or of game creation/start if nobody moved yet
+    pub expiration: Expiration, // an Open game can't be joined past this point and anyone can cancel it
+    pub draw_offer: Option<Addr>, // player with a pending draw offer, withdrawn once the opponent moves
+    pub seeds: Vec<Seed>,         // players' committed seeds, revealed to decide who plays "X"
+    pub use_beacon: bool,         // roles also wait for a drand beacon
+    pub beacon_round: Option<u64>, // drand round deciding the roles, the first one after the game was joined
+    pub randomness: Option<Binary>, // randomness of the submitted beacon
+}
+
+#[cw_serde]
+pub struct Seed {
+    pub player: Addr,
+    pub commitment: String, // hex encoded sha256 of the secret, sent on create/join
+    pub secret: Option<String>, // None until revealed
+    pub pubkey: Option<Binary>, // player's secp256k1 public key, hashed along with the secrets
+}
+
+// layouts stored before the contract was versioned, only read when migrating
+#[cw_serde]
+pub struct LegacyConfig {
+    pub owner: Addr,
+}
+
+#[cw_serde]
+pub struct LegacyGame {
+    pub id: u64,
+    pub players: Vec<Addr>,
+    pub status: GameStatus,
+    pub moves: Vec<String>, // 3x3 board, "-" for empty cells
+    pub next_turn: Option<Addr>,
+    pub winner: Option<Addr>,
+}
+
+#[cw_serde]
+pub enum GameVariant {
+    Classic,
+    // nine classic sub-boards, where each move decides the sub-board the opponent plays next.
+    // moves holds the sub-boards one after another, sub-board 1 in indexes 0-8
+    Ultimate,
+    // completing a line loses
+    Misere,
+    // 4x4x4 cube with 76 winning lines
+    Qubic,
+}
+
+impl fmt::Display for GameVariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameVariant::Classic => write!(f, "classic"),
+            GameVariant::Ultimate => write!(f, "ultimate"),
+            GameVariant::Misere => write!(f, "misere"),
+            GameVariant::Qubic => write!(f, "qubic"),
+        }
+    }
+}
+
+impl Game {
+    // address of the winning player, None for draws, cancelled and unfinished games
+    pub fn winner(&self) -> Option<Addr> {
+        let opponent_of = |by: &Addr| self.players.iter().find(|player| *player != by).cloned();
+        match self.result.as_ref()? {
+            GameResult::XWins => self.players.first().cloned(),
+            GameResult::OWins => self.players.get(1).cloned(),
+            GameResult::Resigned { by } | GameResult::TimedOut { by } => opponent_of(by),
+            GameResult::Unverified { winner } => Some(winner.clone()),
+            GameResult::Draw | GameResult::Cancelled => None,
+        }
+    }
+}
+
+#[cw_serde]
+pub enum GameResult {
+    XWins,
+    OWins,
+    Draw,
+    // player who resigned
+    Resigned { by: Addr },
+    // player who ran out of time to move
+    TimedOut { by: Addr },
+    Cancelled,
+    // winner recorded before versioning with no line on the board backing it, it may have been a
+    // resignation or a misread line
+    Unverified { winner: Addr },
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameResult::XWins => write!(f, "x_wins"),
+            GameResult::OWins => write!(f, "o_wins"),
+            GameResult::Draw => write!(f, "draw"),
+            GameResult::Resigned { .. } => write!(f, "resigned"),
+            GameResult::TimedOut { .. } => write!(f, "timed_out"),
+            GameResult::Cancelled => write!(f, "cancelled"),
+            GameResult::Unverified { .. } => write!(f, "unverified"),
+        }
+    }
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct PlayerStats {
+    pub wins: u64,
+    pub losses: u64, // includes resignations and timeouts
+    pub draws: u64,
+    pub resignations: u64,
+    pub games_played: u64, // completed games only, cancelled games don't count
+}
+
+#[cw_serde]
+pub struct QueueEntry {
+    pub player: Addr,
+    pub commitment: String,     // seed commitment for the matched game
+    pub pubkey: Option<Binary>, // public key for the matched game
+    pub rating: u32,            // player's current rating
+    pub min_rating: u32,        // lowest opponent rating the player accepts
+    pub max_rating: u32,        // highest opponent rating the player accepts
 }
 
 #[cw_serde]
+pub enum Stake {
+    Native(Coin),
+    Cw20(Cw20CoinVerified),
+}
+
+impl Stake {
+    pub fn amount(&self) -> Uint128 {
+        match self {
+            Stake::Native(coin) => coin.amount,
+            Stake::Cw20(cw20) => cw20.amount,
+        }
+    }
+}
+
+impl fmt::Display for Stake {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stake::Native(coin) => write!(f, "{}", coin),
+            Stake::Cw20(cw20) => write!(f, "{}{}", cw20.amount, cw20.address),
+        }
+    }
+}
+
+#[cw_serde]
+#[derive(Default)]
 pub struct InstantiateMsg {
     pub owner: Option<String>,
+    // defaults to one day
+    pub move_timeout: Option<Duration>,
+    // defaults to 100_800 blocks, about a week with 6 second blocks
+    pub open_game_expiry: Option<u64>,
+    // defaults to 1200
+    pub initial_rating: Option<u32>,
+    // defaults to 32
+    pub k_factor: Option<u32>,
+    // games can't wait for beacons unless set
+    pub drand: Option<DrandConfig>,
+    // cw20 contracts accepted for stakes, none unless set
+    pub cw20_tokens: Option<Vec<String>>,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {
+    // legacy games converted by this call, defaults to 100 and is capped at 500. Migrate again
+    // while the response reports legacy_games_pending
+    pub limit: Option<u32>,
 }
 
 #[cw_serde]
@@ -39,27 +252,268 @@ pub enum ExecuteMsg {
     CreateGame(CreateGameMsg),
     JoinGame(JoinGameMsg),
     SubmitMove(SubmitMoveMsg),
+    SubmitUltimateMove(SubmitUltimateMoveMsg),
     Resign(ResignMsg),
+    DeclineGame(DeclineGameMsg),
+    ClaimTimeout(ClaimTimeoutMsg),
+    CancelGame(CancelGameMsg),
+    OfferDraw(OfferDrawMsg),
+    AcceptDraw(AcceptDrawMsg),
+    DeclineDraw(DeclineDrawMsg),
+    EnterQueue(EnterQueueMsg),
+    LeaveQueue(LeaveQueueMsg),
+    RevealSeed(RevealSeedMsg),
+    SubmitBeacon(SubmitBeaconMsg),
+    // owner only
+    UpdateConfig(UpdateConfigMsg),
+    TransferOwnership(TransferOwnershipMsg),
+    AcceptOwnership(AcceptOwnershipMsg),
+    RenounceOwnership(RenounceOwnershipMsg),
+    // cw20 hook, the sent tokens are escrowed as the game stake
+    Receive(Cw20ReceiveMsg),
+}
+
+// messages accepted through the cw20 Receive hook
+#[cw_serde]
+pub enum ReceiveMsg {
+    CreateGame(CreateGameMsg),
+    JoinGame(JoinGameMsg),
 }
 
 #[cw_serde]
+#[derive(Default)]
 pub struct CreateGameMsg {
-    // Feature: create a private game passing opponent Addr
+    // hex encoded sha256 of a secret only the creator knows, revealed once an opponent joins
+    pub commitment: String,
+    // compressed secp256k1 public key of the sender, added to the role hash
+    pub pubkey: Option<Binary>,
+    // creates a private game that only the given address can join
+    pub opponent: Option<String>,
+    // overrides the configured move timeout for this game
+    pub move_timeout: Option<Duration>,
+    // board dimensions and cells in a line needed to win, all default to 3
+    pub rows: Option<u8>,
+    pub cols: Option<u8>,
+    pub win_length: Option<u8>,
+    // defaults to Classic
+    pub variant: Option<GameVariant>,
+    // waits for a drand round after the game is joined to assign roles, defaults to false
+    pub use_beacon: Option<bool>,
 }
 
 #[cw_serde]
 pub struct JoinGameMsg {
     pub game_id: u64,
+    // hex encoded sha256 of a secret only the joiner knows
+    pub commitment: String,
+    // compressed secp256k1 public key of the sender, added to the role hash
+    pub pubkey: Option<Binary>,
 }
 
 #[cw_serde]
 pub struct SubmitMoveMsg {
     pub game_id: u64,
-    // position goes from 1 to 9, being 1 top left and 9 bottom right
-    pub position: u8,
+    // either a position or coordinates must be given.
+    // position goes from 1 to rows * cols * layers row by row and layer by layer, being 1 top left
+    // of the first layer and the last one bottom right of the last layer
+    pub position: Option<u16>,
+    pub coordinates: Option<Coordinates>,
+}
+
+#[cw_serde]
+pub struct Coordinates {
+    // column, row and layer of the cell, all starting from 1 on the top left of the first layer
+    pub x: u8,
+    pub y: u8,
+    pub z: u8,
+}
+
+#[cw_serde]
+pub struct SubmitUltimateMoveMsg {
+    pub game_id: u64,
+    // sub-board and cell within it go from 1 to 9, being 1 top left and 9 bottom right
+    pub sub_board: u8,
+    pub cell: u8,
 }
 
 #[cw_serde]
 pub struct ResignMsg {
     pub game_id: u64,
 }
+
+#[cw_serde]
+pub struct DeclineGameMsg {
+    pub game_id: u64,
+}
+
+#[cw_serde]
+pub struct ClaimTimeoutMsg {
+    pub game_id: u64,
+}
+
+#[cw_serde]
+pub struct CancelGameMsg {
+    pub game_id: u64,
+}
+
+#[cw_serde]
+pub struct OfferDrawMsg {
+    pub game_id: u64,
+}
+
+#[cw_serde]
+pub struct AcceptDrawMsg {
+    pub game_id: u64,
+}
+
+#[cw_serde]
+pub struct DeclineDrawMsg {
+    pub game_id: u64,
+}
+
+#[cw_serde]
+pub struct EnterQueueMsg {
+    // hex encoded sha256 of a secret, revealed once matched
+    pub commitment: String,
+    // compressed secp256k1 public key of the sender, added to the role hash
+    pub pubkey: Option<Binary>,
+    // range of opponent ratings to be matched with, inclusive
+    pub min_rating: u32,
+    pub max_rating: u32,
+}
+
+#[cw_serde]
+pub struct LeaveQueueMsg {}
+
+// fields left out keep their current value
+#[cw_serde]
+#[derive(Default)]
+pub struct UpdateConfigMsg {
+    pub move_timeout: Option<Duration>,
+    pub open_game_expiry: Option<u64>,
+    pub initial_rating: Option<u32>,
+    pub k_factor: Option<u32>,
+    pub drand: Option<DrandConfig>,
+    // replaces the whole list of accepted cw20 contracts
+    pub cw20_tokens: Option<Vec<String>>,
+}
+
+// proposes a new owner, who has to accept before taking over
+#[cw_serde]
+pub struct TransferOwnershipMsg {
+    pub new_owner: String,
+}
+
+#[cw_serde]
+pub struct AcceptOwnershipMsg {}
+
+// leaves the contract without an owner for good
+#[cw_serde]
+pub struct RenounceOwnershipMsg {}
+
+#[cw_serde]
+pub struct RevealSeedMsg {
+    pub game_id: u64,
+    pub secret: String,
+}
+
+#[cw_serde]
+pub struct SubmitBeaconMsg {
+    pub game_id: u64,
+    // drand round and its signature, anyone can submit them
+    pub round: u64,
+    pub signature: Binary,
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(GameResponse)]
+    Game { game_id: u64 },
+    #[returns(ConfigResponse)]
+    Config {},
+    #[returns(StateResponse)]
+    State {},
+    // paginated list of games ordered by id, optionally filtered by status
+    #[returns(ListGamesResponse)]
+    ListGames {
+        status: Option<GameStatus>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    // paginated list of games the given address is playing or has played, ordered by id
+    #[returns(ListGamesResponse)]
+    GamesByPlayer {
+        player: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    #[returns(PlayerStatsResponse)]
+    PlayerStats { address: String },
+    #[returns(RatingResponse)]
+    Rating { address: String },
+    #[returns(QueueStatusResponse)]
+    QueueStatus { address: String },
+    // paginated list of players ordered by wins, most wins first
+    #[returns(LeaderboardResponse)]
+    Leaderboard {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // paginated list of games waiting for a second player that haven't expired, ordered by id
+    #[returns(ListGamesResponse)]
+    OpenGames {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct GameResponse {
+    pub game: Game,
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub owner: Option<Addr>,
+    pub pending_owner: Option<Addr>,
+    pub move_timeout: Duration,
+    pub open_game_expiry: u64,
+    pub initial_rating: u32,
+    pub k_factor: u32,
+    pub drand: Option<DrandConfig>,
+    pub cw20_tokens: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct StateResponse {
+    pub latest_game_id: u64,
+}
+
+#[cw_serde]
+pub struct ListGamesResponse {
+    pub games: Vec<Game>,
+}
+
+#[cw_serde]
+pub struct PlayerStatsResponse {
+    pub address: Addr,
+    pub stats: PlayerStats,
+}
+
+#[cw_serde]
+pub struct LeaderboardResponse {
+    pub players: Vec<PlayerStatsResponse>,
+}
+
+#[cw_serde]
+pub struct RatingResponse {
+    pub address: Addr,
+    pub rating: u32,
+}
+
+#[cw_serde]
+pub struct QueueStatusResponse {
+    // None if the address isn't waiting in the queue
+    pub entry: Option<QueueEntry>,
+}
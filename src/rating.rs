@@ -0,0 +1,42 @@
+use cosmwasm_std::{Decimal, Uint128};
+
+// 10^(1/400) with 18 decimal places, so 10^(diff/400) is a plain integer power of it
+const ELO_BASE: Decimal = Decimal::raw(1_005_773_063_001_738_243);
+
+// rating differences are capped like FIDE does, this also keeps the power far from overflowing
+const MAX_RATING_DIFF: u32 = 800;
+
+// expected score precision, in millionths of a point
+const PRECISION: u128 = 1_000_000;
+
+// Elo rating change of player A after a game against player B. The score is counted in half
+// points: 2 for a win, 1 for a draw and 0 for a loss. Player B's change is the exact opposite.
+// Floats are not allowed in wasm contracts, so the expected score is computed with Decimal.
+pub fn elo_delta(rating_a: u32, rating_b: u32, score_a: u32, k_factor: u32) -> i64 {
+    let diff = rating_a.abs_diff(rating_b).min(MAX_RATING_DIFF);
+    let power = ELO_BASE.pow(diff);
+
+    // expected score of the lower rated player is 1 / (1 + 10^(diff/400))
+    let lower = Decimal::one() / (Decimal::one() + power);
+    let lower = (lower * Uint128::new(PRECISION)).u128() as i64;
+    let expected_a = if rating_a < rating_b {
+        lower
+    } else {
+        PRECISION as i64 - lower
+    };
+
+    // K * (S - E), rounded to the nearest point
+    let actual_a = score_a as i64 * PRECISION as i64 / 2;
+    let delta = k_factor as i64 * (actual_a - expected_a);
+    let half = PRECISION as i64 / 2;
+    if delta >= 0 {
+        (delta + half) / PRECISION as i64
+    } else {
+        (delta - half) / PRECISION as i64
+    }
+}
+
+// applies a rating change without going below zero
+pub fn apply_delta(rating: u32, delta: i64) -> u32 {
+    (rating as i64 + delta).clamp(0, u32::MAX as i64) as u32
+}
@@ -1,9 +1,289 @@
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Binary, Empty, StdError, StdResult, Storage, Timestamp};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use cw_utils::{Duration, Expiration};
+use serde::{Deserialize, Serialize};
 
-use crate::msg::{Config, Game, State};
+use crate::board::{packed, Board};
+use crate::msg::{
+    Config, Game, GameResult, GameStatus, GameVariant, LegacyConfig, LegacyGame, PlayerStats,
+    QueueEntry, Seed, Stake, State,
+};
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
 pub const STATE: Item<State> = Item::new("state");
 
-pub const GAME: Map<u64, Game> = Map::new("game_state");
+// same keys as CONFIG and games() with the layout before versioning, only used to migrate them
+pub const LEGACY_CONFIG: Item<LegacyConfig> = Item::new("config");
+pub const LEGACY_GAMES: Map<u64, LegacyGame> = Map::new("game_state");
+// next legacy game id to convert and the first id past the legacy games, only set until all of
+// them are converted
+pub const LEGACY_MIGRATION: Item<(u64, u64)> = Item::new("legacy_migration");
+
+// Elo rating of every player that completed at least one game
+pub const RATINGS: Map<&Addr, u32> = Map::new("ratings");
+
+// every seed commitment ever made, a secret revealed in one game must not decide another
+pub const COMMITMENTS: Map<&str, Empty> = Map::new("commitments");
+
+// a Game as saved to storage, with its boards packed into bitmasks. Queries return Game, which
+// keeps the boards as lists of cells
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StoredGame {
+    pub id: u64,
+    pub players: Vec<Addr>,
+    pub status: GameStatus,
+    #[serde(with = "packed")]
+    pub moves: Board,
+    pub rows: u8,
+    pub cols: u8,
+    pub layers: u8,
+    pub win_length: u8,
+    pub variant: GameVariant,
+    #[serde(with = "packed")]
+    pub sub_boards: Board,
+    pub next_sub_board: Option<u8>,
+    pub next_turn: Option<Addr>,
+    pub result: Option<GameResult>,
+    pub opponent: Option<Addr>,
+    pub stake: Option<Stake>,
+    pub move_timeout: Duration,
+    pub last_move_height: u64,
+    pub last_move_time: Timestamp,
+    pub expiration: Expiration,
+    pub draw_offer: Option<Addr>,
+    pub seeds: Vec<Seed>,
+    pub use_beacon: bool,
+    pub beacon_round: Option<u64>,
+    pub randomness: Option<Binary>,
+}
+
+impl From<Game> for StoredGame {
+    fn from(game: Game) -> Self {
+        let Game {
+            id,
+            players,
+            status,
+            moves,
+            rows,
+            cols,
+            layers,
+            win_length,
+            variant,
+            sub_boards,
+            next_sub_board,
+            next_turn,
+            result,
+            opponent,
+            stake,
+            move_timeout,
+            last_move_height,
+            last_move_time,
+            expiration,
+            draw_offer,
+            seeds,
+            use_beacon,
+            beacon_round,
+            randomness,
+        } = game;
+        StoredGame {
+            id,
+            players,
+            status,
+            moves,
+            rows,
+            cols,
+            layers,
+            win_length,
+            variant,
+            sub_boards,
+            next_sub_board,
+            next_turn,
+            result,
+            opponent,
+            stake,
+            move_timeout,
+            last_move_height,
+            last_move_time,
+            expiration,
+            draw_offer,
+            seeds,
+            use_beacon,
+            beacon_round,
+            randomness,
+        }
+    }
+}
+
+impl From<StoredGame> for Game {
+    fn from(game: StoredGame) -> Self {
+        let StoredGame {
+            id,
+            players,
+            status,
+            moves,
+            rows,
+            cols,
+            layers,
+            win_length,
+            variant,
+            sub_boards,
+            next_sub_board,
+            next_turn,
+            result,
+            opponent,
+            stake,
+            move_timeout,
+            last_move_height,
+            last_move_time,
+            expiration,
+            draw_offer,
+            seeds,
+            use_beacon,
+            beacon_round,
+            randomness,
+        } = game;
+        Game {
+            id,
+            players,
+            status,
+            moves,
+            rows,
+            cols,
+            layers,
+            win_length,
+            variant,
+            sub_boards,
+            next_sub_board,
+            next_turn,
+            result,
+            opponent,
+            stake,
+            move_timeout,
+            last_move_height,
+            last_move_time,
+            expiration,
+            draw_offer,
+            seeds,
+            use_beacon,
+            beacon_round,
+            randomness,
+        }
+    }
+}
+
+pub struct GameIndexes<'a> {
+    // games by status, keyed on GameStatus' string representation
+    pub status: MultiIndex<'a, String, StoredGame, u64>,
+    // games by player, with one entry per address in Game::players
+    pub player: PlayersIndex<'a>,
+}
+
+impl<'a> IndexList<StoredGame> for GameIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<StoredGame>> + '_> {
+        let v: Vec<&dyn Index<StoredGame>> = vec![&self.status, &self.player];
+        Box::new(v.into_iter())
+    }
+}
+
+// loads and saves games through their stored layout
+pub fn load_game(storage: &dyn Storage, game_id: u64) -> StdResult<Game> {
+    Ok(games().load(storage, game_id)?.into())
+}
+
+pub fn save_game(storage: &mut dyn Storage, game_id: u64, game: &Game) -> StdResult<()> {
+    games().save(storage, game_id, &game.clone().into())
+}
+
+pub fn games<'a>() -> IndexedMap<'a, u64, StoredGame, GameIndexes<'a>> {
+    let indexes = GameIndexes {
+        status: MultiIndex::new(
+            |_pk, game| game.status.to_string(),
+            "game_state",
+            "game_state__status",
+        ),
+        player: PlayersIndex::new("game_state__player"),
+    };
+    IndexedMap::new("game_state", indexes)
+}
+
+pub struct PlayerStatsIndexes<'a> {
+    // players by number of wins, used to rank the leaderboard
+    pub wins: MultiIndex<'a, u64, PlayerStats, &'a Addr>,
+}
+
+impl<'a> IndexList<PlayerStats> for PlayerStatsIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<PlayerStats>> + '_> {
+        let v: Vec<&dyn Index<PlayerStats>> = vec![&self.wins];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn player_stats<'a>() -> IndexedMap<'a, &'a Addr, PlayerStats, PlayerStatsIndexes<'a>> {
+    let indexes = PlayerStatsIndexes {
+        wins: MultiIndex::new(
+            |_pk, stats| stats.wins,
+            "player_stats",
+            "player_stats__wins",
+        ),
+    };
+    IndexedMap::new("player_stats", indexes)
+}
+
+pub struct QueueIndexes<'a> {
+    // waiting players by rating, used to find opponents within a rating range
+    pub rating: MultiIndex<'a, u32, QueueEntry, &'a Addr>,
+}
+
+impl<'a> IndexList<QueueEntry> for QueueIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<QueueEntry>> + '_> {
+        let v: Vec<&dyn Index<QueueEntry>> = vec![&self.rating];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn queue<'a>() -> IndexedMap<'a, &'a Addr, QueueEntry, QueueIndexes<'a>> {
+    let indexes = QueueIndexes {
+        rating: MultiIndex::new(|_pk, entry| entry.rating, "queue", "queue__rating"),
+    };
+    IndexedMap::new("queue", indexes)
+}
+
+// MultiIndex only supports a single index key per record, so players are indexed through a
+// (player, game_id) map that gets an entry for every address in Game::players
+pub struct PlayersIndex<'a> {
+    pub idx: Map<'a, (Addr, u64), Empty>,
+}
+
+impl<'a> PlayersIndex<'a> {
+    pub const fn new(namespace: &'a str) -> Self {
+        PlayersIndex {
+            idx: Map::new(namespace),
+        }
+    }
+}
+
+impl<'a> Index<StoredGame> for PlayersIndex<'a> {
+    fn save(&self, store: &mut dyn Storage, pk: &[u8], data: &StoredGame) -> StdResult<()> {
+        let game_id = game_id_from_pk(pk)?;
+        for player in data.players.iter() {
+            self.idx.save(store, (player.clone(), game_id), &Empty {})?;
+        }
+        Ok(())
+    }
+
+    fn remove(&self, store: &mut dyn Storage, pk: &[u8], old_data: &StoredGame) -> StdResult<()> {
+        let game_id = game_id_from_pk(pk)?;
+        for player in old_data.players.iter() {
+            self.idx.remove(store, (player.clone(), game_id));
+        }
+        Ok(())
+    }
+}
+
+fn game_id_from_pk(pk: &[u8]) -> StdResult<u64> {
+    let bytes: [u8; 8] = pk
+        .try_into()
+        .map_err(|_| StdError::generic_err("invalid game primary key"))?;
+    Ok(u64::from_be_bytes(bytes))
+}
@@ -1,20 +1,76 @@
-#[cfg(test)]
-use crate::{contract::instantiate, msg::InstantiateMsg};
 use crate::{
-    contract::{create_game, join_game, resign, submit_move},
-    msg::{CreateGameMsg, GameStatus, JoinGameMsg, ResignMsg, SubmitMoveMsg},
-    state::{CONFIG, GAME, STATE},
+    board::{packed, Board, Cell},
+    contract::{
+        accept_draw, cancel_game, claim_timeout, create_game, decline_draw, decline_game,
+        enter_queue, execute, join_game, leave_queue, migrate, offer_draw, query, resign,
+        reveal_seed, submit_beacon, submit_move, submit_ultimate_move,
+    },
+    msg::{
+        AcceptDrawMsg, AcceptOwnershipMsg, CancelGameMsg, ClaimTimeoutMsg, ConfigResponse,
+        Coordinates, CreateGameMsg, DeclineDrawMsg, DeclineGameMsg, DrandConfig, EnterQueueMsg,
+        ExecuteMsg, GameResponse, GameResult, GameStatus, GameVariant, JoinGameMsg,
+        LeaderboardResponse, LeaveQueueMsg, LegacyConfig, LegacyGame, ListGamesResponse,
+        MigrateMsg, OfferDrawMsg, PlayerStats, PlayerStatsResponse, QueryMsg, QueueEntry,
+        QueueStatusResponse, RatingResponse, ReceiveMsg, RenounceOwnershipMsg, ResignMsg,
+        RevealSeedMsg, Stake, State, StateResponse, SubmitBeaconMsg, SubmitMoveMsg,
+        SubmitUltimateMoveMsg, TransferOwnershipMsg, UpdateConfigMsg,
+    },
+    rating::{apply_delta, elo_delta},
+    state::{games, load_game, queue, save_game, CONFIG, LEGACY_CONFIG, LEGACY_GAMES, STATE},
     ContractError,
 };
+#[cfg(test)]
+use crate::{contract::instantiate, msg::InstantiateMsg};
 use cosmwasm_std::{
-    testing::{mock_dependencies, mock_env, mock_info},
-    Addr, Response,
+    attr, coin, coins, from_binary,
+    testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage},
+    to_binary, to_vec, Addr, Api, BankMsg, Binary, CanonicalAddr, Empty, Env, MessageInfo,
+    OwnedDeps, RecoverPubkeyError, Response, StdError, StdResult, Storage, SubMsg, Timestamp,
+    Uint128, VerificationError,
 };
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use cw_utils::{Duration, PaymentError};
+use ripemd::Ripemd160;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::HashSet, marker::PhantomData};
+
+// each test player commits to a secret derived from their address
+// a fresh secret for every game, commitments can't be reused
+fn secret(info: &MessageInfo, game_id: u64) -> String {
+    match game_id {
+        0 => format!("{}_secret", info.sender),
+        _ => format!("{}_secret_{}", info.sender, game_id),
+    }
+}
+
+fn commitment(info: &MessageInfo, game_id: u64) -> String {
+    format!("{:x}", Sha256::digest(secret(info, game_id)))
+}
+
+// reveals every committed seed of a game, starting it
+fn reveal_seeds<A: Api>(
+    deps: &mut OwnedDeps<MockStorage, A, MockQuerier>,
+    env: &Env,
+    game_id: u64,
+) {
+    let game = load_game(&deps.storage, game_id).unwrap();
+    for seed in game.seeds {
+        let info = mock_info(seed.player.as_str(), &[]);
+        let msg = RevealSeedMsg {
+            game_id,
+            secret: secret(&info, game_id),
+        };
+        reveal_seed(deps.as_mut(), env.clone(), info, msg).unwrap();
+    }
+}
 
 #[test]
 fn proper_instantiation() {
     let mut deps = mock_dependencies();
-    let instantiate_msg = InstantiateMsg { owner: None };
+    let instantiate_msg = InstantiateMsg::default();
     let admin_info = mock_info("instantiatoor", &[]);
 
     let res = instantiate(
@@ -35,7 +91,113 @@ fn proper_instantiation() {
     // attributes, latest_game_id, and contract owner as expected & state
     assert_eq!(res_expected, res);
     assert_eq!(state.latest_game_id, 0);
-    assert_eq!(config.owner, "instantiatoor");
+    assert_eq!(config.owner, Some(Addr::unchecked("instantiatoor")));
+    assert_eq!(config.move_timeout, Duration::Time(86_400));
+
+    // contract version is recorded for migrations
+    let version = get_contract_version(&deps.storage).unwrap();
+    assert_eq!(version.contract, "crates.io:tic-tac-toe");
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn administering_the_contract() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let owner_info = mock_info("instantiatoor", &[]);
+    let new_owner_info = mock_info("new_owner", &[]);
+    let player_info = mock_info("player_1", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        owner_info.clone(),
+        InstantiateMsg::default(),
+    )
+    .unwrap();
+    let config = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>| -> ConfigResponse {
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap()
+    };
+
+    // Only the owner can update the config
+    let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        move_timeout: Some(Duration::Height(100)),
+        k_factor: Some(16),
+        ..Default::default()
+    });
+    let err = execute(deps.as_mut(), env.clone(), player_info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = execute(deps.as_mut(), env.clone(), owner_info.clone(), msg).unwrap();
+    assert_eq!(res.attributes, vec![attr("action", "update_config")]);
+    let res = config(&deps);
+    assert_eq!(res.move_timeout, Duration::Height(100));
+    assert_eq!(res.k_factor, 16);
+    assert_eq!(res.initial_rating, 1200);
+
+    // Updates are validated like at instantiation
+    let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        open_game_expiry: Some(0),
+        ..Default::default()
+    });
+    let err = execute(deps.as_mut(), env.clone(), owner_info.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidTimeout {});
+
+    let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        open_game_expiry: Some(u64::MAX),
+        ..Default::default()
+    });
+    let err = execute(deps.as_mut(), env.clone(), owner_info.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::TimeoutTooLong {});
+
+    // Proposing a new owner leaves the current one in charge until it accepts
+    let msg = ExecuteMsg::TransferOwnership(TransferOwnershipMsg {
+        new_owner: "new_owner".to_string(),
+    });
+    let err = execute(deps.as_mut(), env.clone(), player_info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(deps.as_mut(), env.clone(), owner_info.clone(), msg).unwrap();
+
+    let res = config(&deps);
+    assert_eq!(res.owner, Some(owner_info.sender.clone()));
+    assert_eq!(res.pending_owner, Some(new_owner_info.sender.clone()));
+
+    // Nobody else can accept the transfer
+    let msg = ExecuteMsg::AcceptOwnership(AcceptOwnershipMsg {});
+    let err = execute(deps.as_mut(), env.clone(), player_info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let res = execute(deps.as_mut(), env.clone(), new_owner_info.clone(), msg).unwrap();
+    assert!(res.attributes.contains(&attr("owner", "new_owner")));
+    let res = config(&deps);
+    assert_eq!(res.owner, Some(new_owner_info.sender.clone()));
+    assert_eq!(res.pending_owner, None);
+
+    // The previous owner lost its powers
+    let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg::default());
+    let err = execute(deps.as_mut(), env.clone(), owner_info.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    // Renouncing drops the owner along with any pending transfer
+    let msg = ExecuteMsg::TransferOwnership(TransferOwnershipMsg {
+        new_owner: "player_1".to_string(),
+    });
+    execute(deps.as_mut(), env.clone(), new_owner_info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::RenounceOwnership(RenounceOwnershipMsg {});
+    let err = execute(deps.as_mut(), env.clone(), player_info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    execute(deps.as_mut(), env.clone(), new_owner_info.clone(), msg).unwrap();
+
+    let res = config(&deps);
+    assert_eq!((res.owner, res.pending_owner), (None, None));
+
+    let msg = ExecuteMsg::AcceptOwnership(AcceptOwnershipMsg {});
+    let err = execute(deps.as_mut(), env.clone(), player_info, msg).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg::default());
+    let err = execute(deps.as_mut(), env, new_owner_info, msg).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
 }
 
 #[test]
@@ -44,7 +206,7 @@ fn creating_a_game() {
     let env = mock_env();
 
     // Instantiating
-    let instantiate_msg = InstantiateMsg { owner: None };
+    let instantiate_msg = InstantiateMsg::default();
     let admin_info = mock_info("instantiatoor", &[]);
 
     instantiate(
@@ -57,16 +219,20 @@ fn creating_a_game() {
 
     // Creating a game
     let info = mock_info("player_1", &[]);
-    let msg = CreateGameMsg {};
+    let msg = CreateGameMsg {
+        commitment: commitment(&info, 0),
+        ..Default::default()
+    };
 
     let res = create_game(deps.as_mut(), env, info, msg).unwrap();
     let res_expected: Response = Response::new()
         .add_attribute("action", "create_game")
         .add_attribute("game_id", "0")
-        .add_attribute("players", "player_1");
+        .add_attribute("players", "player_1")
+        .add_attribute("variant", "classic");
 
     let state = STATE.load(&deps.storage).unwrap();
-    let game = GAME.load(&deps.storage, 0).unwrap();
+    let game = load_game(&deps.storage, 0).unwrap();
 
     // attributes as expected & game_id correct & latest_game updated
     assert_eq!(res_expected, res);
@@ -80,7 +246,7 @@ fn joining_a_game() {
     let env = mock_env();
 
     // Instantiating
-    let instantiate_msg = InstantiateMsg { owner: None };
+    let instantiate_msg = InstantiateMsg::default();
     let admin_info = mock_info("instantiatoor", &[]);
 
     instantiate(
@@ -91,24 +257,87 @@ fn joining_a_game() {
     )
     .unwrap();
 
+    // Creating a game with a commitment that isn't a sha256 hash fails
+    let info_1 = mock_info("player_1", &[]);
+    let msg = CreateGameMsg {
+        commitment: "player_1_secret".to_string(),
+        ..Default::default()
+    };
+
+    let res = create_game(deps.as_mut(), env.clone(), info_1.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::InvalidCommitment {});
+
     // Creating a game
-    let info = mock_info("player_1", &[]);
-    let msg = CreateGameMsg {};
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        ..Default::default()
+    };
 
-    create_game(deps.as_mut(), env.clone(), info, msg).unwrap();
+    create_game(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
 
     // Joining an existing game
-    let info = mock_info("player_2", &[]);
-    let msg = JoinGameMsg { game_id: 0 };
+    let info_2 = mock_info("player_2", &[]);
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_2, 0),
+        pubkey: None,
+    };
 
-    let res = join_game(deps.as_mut(), env, info, msg).unwrap();
+    let res = join_game(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
 
     let res_expected: Response = Response::new()
         .add_attribute("action", "join_game")
         .add_attribute("game_id", "0")
+        .add_attribute("variant", "classic");
+
+    let game = load_game(&deps.storage, 0).unwrap();
+
+    // attributes as expected & roles wait for both seeds
+    assert_eq!(res_expected, res);
+    assert_eq!(game.status, GameStatus::Revealing);
+    assert_eq!(game.next_turn, None);
+
+    // Revealing a secret that doesn't match the commitment fails
+    let msg = RevealSeedMsg {
+        game_id: 0,
+        secret: secret(&info_2, 0),
+    };
+    let res = reveal_seed(deps.as_mut(), env.clone(), info_1.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::SeedMismatch {});
+
+    // Revealing as someone else than the players fails
+    let msg = RevealSeedMsg {
+        game_id: 0,
+        secret: secret(&info_1, 0),
+    };
+    let res = reveal_seed(deps.as_mut(), env.clone(), mock_info("player_3", &[]), msg);
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    // Revealing the creator's seed
+    let msg = RevealSeedMsg {
+        game_id: 0,
+        secret: secret(&info_1, 0),
+    };
+    reveal_seed(deps.as_mut(), env.clone(), info_1.clone(), msg.clone()).unwrap();
+
+    // Revealing twice fails
+    let res = reveal_seed(deps.as_mut(), env.clone(), info_1, msg);
+    assert_eq!(res.unwrap_err(), ContractError::AlreadyRevealed {});
+
+    // Revealing the joiner's seed starts the game
+    let msg = RevealSeedMsg {
+        game_id: 0,
+        secret: secret(&info_2, 0),
+    };
+    let res = reveal_seed(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+
+    let res_expected: Response = Response::new()
+        .add_attribute("action", "reveal_seed")
+        .add_attribute("game_id", "0")
+        .add_attribute("player", "player_2")
         .add_attribute("X", "player_2");
 
-    let game = GAME.load(&deps.storage, 0).unwrap();
+    let game = load_game(&deps.storage, 0).unwrap();
 
     // attributes as expected & player_2 next turn & players in the correct order
     assert_eq!(res_expected, res);
@@ -116,6 +345,15 @@ fn joining_a_game() {
     assert_eq!(game.next_turn, Some(Addr::unchecked("player_2")));
     assert_eq!(game.players[1], Addr::unchecked("player_1"));
     assert_eq!(game.players[0], Addr::unchecked("player_2"));
+
+    // Creating a game with the commitment revealed in this one fails
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_2, 0),
+        ..Default::default()
+    };
+
+    let res = create_game(deps.as_mut(), env, info_2, msg);
+    assert_eq!(res.unwrap_err(), ContractError::CommitmentUsed {});
 }
 
 #[test]
@@ -124,7 +362,7 @@ fn submitting_a_move() {
     let env = mock_env();
 
     // Instantiating
-    let instantiate_msg = InstantiateMsg { owner: None };
+    let instantiate_msg = InstantiateMsg::default();
     let admin_info = mock_info("instantiatoor", &[]);
 
     instantiate(
@@ -137,20 +375,29 @@ fn submitting_a_move() {
 
     // Creating a game
     let info = mock_info("player_1", &[]);
-    let msg = CreateGameMsg {};
+    let msg = CreateGameMsg {
+        commitment: commitment(&info, 0),
+        ..Default::default()
+    };
 
     create_game(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     // Joining an existing game
     let info = mock_info("player_2", &[]);
-    let msg = JoinGameMsg { game_id: 0 };
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info, 0),
+        pubkey: None,
+    };
 
     join_game(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    reveal_seeds(&mut deps, &env, 0);
 
     // Submitting a move
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 5,
+        position: Some(5),
+        coordinates: None,
     };
     let res = submit_move(deps.as_mut(), env, info, msg).unwrap();
 
@@ -160,7 +407,7 @@ fn submitting_a_move() {
         .add_attribute("position", "5")
         .add_attribute("role", "X");
 
-    let game = GAME.load(&deps.storage, 0).unwrap();
+    let game = load_game(&deps.storage, 0).unwrap();
 
     // attributes as expected & game still in progress & player_1 next turn & players in the correct order
     assert_eq!(res_expected, res);
@@ -176,7 +423,7 @@ fn winning_a_game() {
     let env = mock_env();
 
     // Instantiating
-    let instantiate_msg = InstantiateMsg { owner: None };
+    let instantiate_msg = InstantiateMsg::default();
     let admin_info = mock_info("instantiatoor", &[]);
 
     instantiate(
@@ -189,78 +436,95 @@ fn winning_a_game() {
 
     // Creating a game
     let info_1 = mock_info("player_1", &[]);
-    let msg = CreateGameMsg {};
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        ..Default::default()
+    };
 
     create_game(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
 
     // Joining an existing game
     let info_2 = mock_info("player_2", &[]);
-    let msg = JoinGameMsg { game_id: 0 };
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_2, 0),
+        pubkey: None,
+    };
 
     join_game(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+    reveal_seeds(&mut deps, &env, 0);
 
     // Submitting 1st move
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 1,
+        position: Some(1),
+        coordinates: None,
     };
     submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
 
     // Submitting 2nd move
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 2,
+        position: Some(2),
+        coordinates: None,
     };
     submit_move(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
 
     // Submitting 3rd move
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 3,
+        position: Some(3),
+        coordinates: None,
     };
     submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
 
     // Submitting 4th move
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 4,
+        position: Some(4),
+        coordinates: None,
     };
     submit_move(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
 
     // Submitting 5th move
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 5,
+        position: Some(5),
+        coordinates: None,
     };
     submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
 
     // Submitting 6th move
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 6,
+        position: Some(6),
+        coordinates: None,
     };
     submit_move(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
 
     // Submitting 7th move
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 7,
+        position: Some(7),
+        coordinates: None,
     };
     submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
 
     // Submitting 8th move should fail as the game should be already completed
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 6,
+        position: Some(6),
+        coordinates: None,
     };
     let res = submit_move(deps.as_mut(), env.clone(), info_1.clone(), msg);
 
-    let game = GAME.load(&deps.storage, 0).unwrap();
+    let game = load_game(&deps.storage, 0).unwrap();
 
     // game status completed & 8th move should throw an error & player_2 wins
     assert_eq!(game.status, GameStatus::Completed);
     assert_eq!(res.unwrap_err(), ContractError::GameNotInProgress {});
-    assert_eq!(game.winner.unwrap(), Addr::unchecked("player_2"));
+    assert_eq!(game.result, Some(GameResult::XWins));
+    assert_eq!(game.winner().unwrap(), Addr::unchecked("player_2"));
 }
 
 #[test]
@@ -269,7 +533,7 @@ fn drawing_a_game() {
     let env = mock_env();
 
     // Instantiating
-    let instantiate_msg = InstantiateMsg { owner: None };
+    let instantiate_msg = InstantiateMsg::default();
     let admin_info = mock_info("instantiatoor", &[]);
 
     instantiate(
@@ -282,84 +546,340 @@ fn drawing_a_game() {
 
     // Creating a game
     let info_1 = mock_info("player_1", &[]);
-    let msg = CreateGameMsg {};
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        ..Default::default()
+    };
 
     create_game(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
 
     // Joining an existing game
     let info_2 = mock_info("player_2", &[]);
-    let msg = JoinGameMsg { game_id: 0 };
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_2, 0),
+        pubkey: None,
+    };
+
+    join_game(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+    reveal_seeds(&mut deps, &env, 0);
+
+    // Submitting 1st move
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(1),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+
+    // Submitting 2nd move
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(2),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    // Submitting 3rd move
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(3),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+
+    // Submitting 4th move
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(4),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    // Submitting 5th move
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(5),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+
+    // Submitting 6th move
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(7),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    // Submitting 7th move
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(6),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+
+    // Submitting 8th move
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(9),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    // Submitting 9th move
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(8),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+
+    let game = load_game(&deps.storage, 0).unwrap();
+
+    // game completed as a draw & no winner
+    assert_eq!(game.status, GameStatus::Completed);
+    assert_eq!(game.result, Some(GameResult::Draw));
+    assert_eq!(game.winner(), None);
+}
+
+#[test]
+fn winning_a_misere_game() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Creating a misère game
+    let info_1 = mock_info("player_1", &[]);
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        variant: Some(GameVariant::Misere),
+        ..Default::default()
+    };
+
+    let res = create_game(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+    assert!(res.attributes.contains(&attr("variant", "misere")));
+
+    // Joining an existing game
+    let info_2 = mock_info("player_2", &[]);
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_2, 0),
+        pubkey: None,
+    };
 
     join_game(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+    reveal_seeds(&mut deps, &env, 0);
+
+    // Submitting 1st move
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(1),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+
+    // Submitting 2nd move
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(2),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    // Submitting 3rd move
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(3),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+
+    // Submitting 4th move
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(4),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    // Submitting 5th move
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(5),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+
+    // Submitting 6th move
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(6),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    // Submitting 7th move, completing a diagonal
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(7),
+        coordinates: None,
+    };
+    let res = submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+    assert!(res.attributes.contains(&attr("result", "o_wins")));
+
+    // Submitting 8th move should fail as the game should be already completed
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(6),
+        coordinates: None,
+    };
+    let res = submit_move(deps.as_mut(), env.clone(), info_1.clone(), msg);
+
+    let game = load_game(&deps.storage, 0).unwrap();
+
+    // game status completed & 8th move should throw an error & player_1 wins as "X" completed a line
+    assert_eq!(game.status, GameStatus::Completed);
+    assert_eq!(res.unwrap_err(), ContractError::GameNotInProgress {});
+    assert_eq!(game.result, Some(GameResult::OWins));
+    assert_eq!(game.winner().unwrap(), Addr::unchecked("player_1"));
+
+    // the variant is part of the queried game
+    let query_msg = QueryMsg::Game { game_id: 0 };
+    let res: GameResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), query_msg).unwrap()).unwrap();
+    assert_eq!(res.game.variant, GameVariant::Misere);
+}
+
+#[test]
+fn drawing_a_misere_game() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Creating a misère game
+    let info_1 = mock_info("player_1", &[]);
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        variant: Some(GameVariant::Misere),
+        ..Default::default()
+    };
+
+    create_game(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    // Joining an existing game
+    let info_2 = mock_info("player_2", &[]);
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_2, 0),
+        pubkey: None,
+    };
+
+    let res = join_game(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+    reveal_seeds(&mut deps, &env, 0);
+    assert!(res.attributes.contains(&attr("variant", "misere")));
 
     // Submitting 1st move
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 1,
+        position: Some(1),
+        coordinates: None,
     };
     submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
 
     // Submitting 2nd move
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 2,
+        position: Some(2),
+        coordinates: None,
     };
     submit_move(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
 
     // Submitting 3rd move
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 3,
+        position: Some(3),
+        coordinates: None,
     };
     submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
 
     // Submitting 4th move
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 4,
+        position: Some(4),
+        coordinates: None,
     };
     submit_move(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
 
     // Submitting 5th move
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 5,
+        position: Some(5),
+        coordinates: None,
     };
     submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
 
     // Submitting 6th move
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 7,
+        position: Some(7),
+        coordinates: None,
     };
     submit_move(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
 
     // Submitting 7th move
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 6,
+        position: Some(6),
+        coordinates: None,
     };
     submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
 
     // Submitting 8th move
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 9,
+        position: Some(9),
+        coordinates: None,
     };
     submit_move(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
 
     // Submitting 9th move
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 8,
+        position: Some(8),
+        coordinates: None,
     };
     submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
 
-    let game = GAME.load(&deps.storage, 0).unwrap();
+    let game = load_game(&deps.storage, 0).unwrap();
 
-    // game completed & no winner
+    // game completed as a draw & no winner
     assert_eq!(game.status, GameStatus::Completed);
-    assert_eq!(game.winner, None);
+    assert_eq!(game.result, Some(GameResult::Draw));
+    assert_eq!(game.winner(), None);
 }
 
 #[test]
@@ -368,7 +888,7 @@ fn resigning_after_one_move() {
     let env = mock_env();
 
     // Instantiating
-    let instantiate_msg = InstantiateMsg { owner: None };
+    let instantiate_msg = InstantiateMsg::default();
     let admin_info = mock_info("instantiatoor", &[]);
 
     instantiate(
@@ -381,20 +901,29 @@ fn resigning_after_one_move() {
 
     // Creating a game
     let info = mock_info("player_1", &[]);
-    let msg = CreateGameMsg {};
+    let msg = CreateGameMsg {
+        commitment: commitment(&info, 0),
+        ..Default::default()
+    };
 
     create_game(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     // Joining an existing game
     let info = mock_info("player_2", &[]);
-    let msg = JoinGameMsg { game_id: 0 };
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info, 0),
+        pubkey: None,
+    };
 
     join_game(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    reveal_seeds(&mut deps, &env, 0);
 
     // Submitting a move
     let msg = SubmitMoveMsg {
         game_id: 0,
-        position: 5,
+        position: Some(5),
+        coordinates: None,
     };
 
     submit_move(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
@@ -406,12 +935,3103 @@ fn resigning_after_one_move() {
     let res_expected: Response = Response::new()
         .add_attribute("action", "resign")
         .add_attribute("game_id", "0")
-        .add_attribute("winner", "player_1");
+        .add_attribute("winner", "player_1")
+        .add_attribute("result", "resigned")
+        .add_attribute("rating_delta", "player_2:-16")
+        .add_attribute("rating_delta", "player_1:+16");
 
-    let game = GAME.load(&deps.storage, 0).unwrap();
+    let game = load_game(&deps.storage, 0).unwrap();
 
     // attributes as expected & game completed & player_1 won
     assert_eq!(res_expected, res);
     assert_eq!(game.status, GameStatus::Completed);
-    assert_eq!(game.winner.unwrap(), Addr::unchecked("player_1"));
+    assert_eq!(
+        game.result,
+        Some(GameResult::Resigned {
+            by: Addr::unchecked("player_2")
+        })
+    );
+    assert_eq!(game.winner().unwrap(), Addr::unchecked("player_1"));
+}
+
+#[test]
+fn rejecting_non_participants() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("instantiatoor", &[]),
+        InstantiateMsg::default(),
+    )
+    .unwrap();
+
+    let info_1 = mock_info("player_1", &[]);
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        ..Default::default()
+    };
+    create_game(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    // The creator can't join their own game
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_1, 0),
+        pubkey: None,
+    };
+    let err = join_game(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::CantJoinOwnGame {});
+
+    let info_2 = mock_info("player_2", &[]);
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_2, 0),
+        pubkey: None,
+    };
+    join_game(deps.as_mut(), env.clone(), info_2, msg).unwrap();
+    reveal_seeds(&mut deps, &env, 0);
+
+    // Somebody else can't resign on behalf of a player
+    let info_3 = mock_info("player_3", &[]);
+    let msg = ResignMsg { game_id: 0 };
+    let err = resign(deps.as_mut(), env.clone(), info_3, msg).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let game = load_game(&deps.storage, 0).unwrap();
+    assert_eq!(game.status, GameStatus::InProgress);
+
+    // A game that lost a player can't be played or finished
+    let mut game = load_game(&deps.storage, 0).unwrap();
+    game.players.retain(|player| *player == info_1.sender);
+    game.next_turn = Some(info_1.sender.clone());
+    save_game(&mut deps.storage, 0, &game).unwrap();
+
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(1),
+        coordinates: None,
+    };
+    let err = submit_move(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidPlayers {});
+
+    let msg = ResignMsg { game_id: 0 };
+    let err = resign(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidPlayers {});
+
+    // Nor can one where the same player sits in both slots
+    game.players = vec![info_1.sender.clone(), info_1.sender.clone()];
+    save_game(&mut deps.storage, 0, &game).unwrap();
+
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(1),
+        coordinates: None,
+    };
+    let err = submit_move(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidPlayers {});
+
+    let msg = ResignMsg { game_id: 0 };
+    let err = resign(deps.as_mut(), env, info_1, msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidPlayers {});
+}
+
+#[test]
+fn querying_games() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Creating three games
+    for (game_id, player) in (0..).zip(["player_1", "player_2", "player_3"]) {
+        let info = mock_info(player, &[]);
+        let msg = CreateGameMsg {
+            commitment: commitment(&info, game_id),
+            ..Default::default()
+        };
+        create_game(deps.as_mut(), env.clone(), info, msg).unwrap();
+    }
+
+    // Joining game 1
+    let info = mock_info("player_4", &[]);
+    let msg = JoinGameMsg {
+        game_id: 1,
+        commitment: commitment(&info, 1),
+        pubkey: None,
+    };
+
+    join_game(deps.as_mut(), env.clone(), info, msg).unwrap();
+    reveal_seeds(&mut deps, &env, 1);
+
+    // Querying config and state
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config.owner, Some(Addr::unchecked("instantiatoor")));
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::State {}).unwrap();
+    let state: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state.latest_game_id, 3);
+
+    // Querying a single game
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Game { game_id: 1 }).unwrap();
+    let game: GameResponse = from_binary(&res).unwrap();
+    assert_eq!(game.game.id, 1);
+    assert_eq!(game.game.status, GameStatus::InProgress);
+
+    // Listing open games only
+    let msg = QueryMsg::ListGames {
+        status: Some(GameStatus::Open),
+        start_after: None,
+        limit: None,
+    };
+    let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let list: ListGamesResponse = from_binary(&res).unwrap();
+    let ids: Vec<u64> = list.games.iter().map(|game| game.id).collect();
+    assert_eq!(ids, vec![0, 2]);
+
+    // Paginating over every game
+    let msg = QueryMsg::ListGames {
+        status: None,
+        start_after: Some(0),
+        limit: Some(1),
+    };
+    let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let list: ListGamesResponse = from_binary(&res).unwrap();
+    let ids: Vec<u64> = list.games.iter().map(|game| game.id).collect();
+    assert_eq!(ids, vec![1]);
+
+    // Querying a game that doesn't exist fails
+    let res = query(deps.as_ref(), env, QueryMsg::Game { game_id: 9 });
+    assert!(res.is_err());
+}
+
+#[test]
+fn querying_games_by_player_and_status() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Creating three games
+    for (game_id, player) in (0..).zip(["player_1", "player_2", "player_1"]) {
+        let info = mock_info(player, &[]);
+        let msg = CreateGameMsg {
+            commitment: commitment(&info, game_id),
+            ..Default::default()
+        };
+        create_game(deps.as_mut(), env.clone(), info, msg).unwrap();
+    }
+
+    // player_1 joins game 1, created by player_2
+    let info = mock_info("player_1", &[]);
+    let msg = JoinGameMsg {
+        game_id: 1,
+        commitment: commitment(&info, 1),
+        pubkey: None,
+    };
+
+    join_game(deps.as_mut(), env.clone(), info, msg).unwrap();
+    reveal_seeds(&mut deps, &env, 1);
+
+    // player_1 takes part in every game
+    let msg = QueryMsg::GamesByPlayer {
+        player: "player_1".to_string(),
+        start_after: None,
+        limit: None,
+    };
+    let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let list: ListGamesResponse = from_binary(&res).unwrap();
+    let ids: Vec<u64> = list.games.iter().map(|game| game.id).collect();
+    assert_eq!(ids, vec![0, 1, 2]);
+
+    // player_2 only takes part in game 1
+    let msg = QueryMsg::GamesByPlayer {
+        player: "player_2".to_string(),
+        start_after: None,
+        limit: None,
+    };
+    let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let list: ListGamesResponse = from_binary(&res).unwrap();
+    let ids: Vec<u64> = list.games.iter().map(|game| game.id).collect();
+    assert_eq!(ids, vec![1]);
+
+    // game 1 left the open index once it was joined
+    let msg = QueryMsg::OpenGames {
+        start_after: None,
+        limit: None,
+    };
+    let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let list: ListGamesResponse = from_binary(&res).unwrap();
+    let ids: Vec<u64> = list.games.iter().map(|game| game.id).collect();
+    assert_eq!(ids, vec![0, 2]);
+
+    // paginating over open games
+    let msg = QueryMsg::OpenGames {
+        start_after: Some(0),
+        limit: Some(1),
+    };
+    let res = query(deps.as_ref(), env, msg).unwrap();
+    let list: ListGamesResponse = from_binary(&res).unwrap();
+    let ids: Vec<u64> = list.games.iter().map(|game| game.id).collect();
+    assert_eq!(ids, vec![2]);
+}
+
+#[test]
+fn joining_a_private_game() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Creating a private game
+    let info = mock_info("player_1", &[]);
+    let msg = CreateGameMsg {
+        commitment: commitment(&info, 0),
+        opponent: Some("player_2".to_string()),
+        ..Default::default()
+    };
+
+    let res = create_game(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let res_expected: Response = Response::new()
+        .add_attribute("action", "create_game")
+        .add_attribute("game_id", "0")
+        .add_attribute("players", "player_1")
+        .add_attribute("variant", "classic")
+        .add_attribute("opponent", "player_2");
+
+    assert_eq!(res_expected, res);
+
+    // Joining as an uninvited player fails
+    let info = mock_info("player_3", &[]);
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info, 0),
+        pubkey: None,
+    };
+
+    let res = join_game(deps.as_mut(), env.clone(), info, msg);
+    assert_eq!(res.unwrap_err(), ContractError::NotInvited {});
+
+    // Joining as the invited opponent
+    let info = mock_info("player_2", &[]);
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info, 0),
+        pubkey: None,
+    };
+
+    join_game(deps.as_mut(), env.clone(), info, msg).unwrap();
+    reveal_seeds(&mut deps, &env, 0);
+
+    let game = load_game(&deps.storage, 0).unwrap();
+
+    // game in progress between the creator and the invited opponent
+    assert_eq!(game.status, GameStatus::InProgress);
+    assert!(game.players.contains(&Addr::unchecked("player_1")));
+    assert!(game.players.contains(&Addr::unchecked("player_2")));
+}
+
+#[test]
+fn declining_a_private_game() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Creating a private game
+    let info = mock_info("player_1", &[]);
+    let msg = CreateGameMsg {
+        commitment: commitment(&info, 0),
+        opponent: Some("player_2".to_string()),
+        ..Default::default()
+    };
+
+    create_game(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // Declining as someone other than the invited opponent fails
+    let info = mock_info("player_1", &[]);
+    let msg = DeclineGameMsg { game_id: 0 };
+
+    let res = decline_game(deps.as_mut(), env.clone(), info, msg);
+    assert_eq!(res.unwrap_err(), ContractError::NotInvited {});
+
+    // Declining as the invited opponent
+    let info = mock_info("player_2", &[]);
+    let msg = DeclineGameMsg { game_id: 0 };
+
+    let res = decline_game(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let res_expected: Response = Response::new()
+        .add_attribute("action", "decline_game")
+        .add_attribute("game_id", "0")
+        .add_attribute("result", "cancelled");
+
+    // attributes as expected & game kept as cancelled
+    assert_eq!(res_expected, res);
+    let game = load_game(&deps.storage, 0).unwrap();
+    assert_eq!(game.status, GameStatus::Cancelled);
+    assert_eq!(game.result, Some(GameResult::Cancelled));
+
+    // Declining it again fails
+    let info = mock_info("player_2", &[]);
+    let msg = DeclineGameMsg { game_id: 0 };
+
+    let res = decline_game(deps.as_mut(), env, info, msg);
+    assert_eq!(res.unwrap_err(), ContractError::GameNotOpen {});
+}
+
+#[test]
+fn resigning_a_wagered_game() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Creating a game with a stake
+    let info = mock_info("player_1", &coins(100, "ucosm"));
+    let msg = CreateGameMsg {
+        commitment: commitment(&info, 0),
+        ..Default::default()
+    };
+
+    create_game(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let game = load_game(&deps.storage, 0).unwrap();
+    assert_eq!(game.stake, Some(Stake::Native(coin(100, "ucosm"))));
+
+    // Joining with a different amount fails
+    let info = mock_info("player_2", &coins(50, "ucosm"));
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info, 0),
+        pubkey: None,
+    };
+
+    let res = join_game(deps.as_mut(), env.clone(), info, msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::StakeMismatch {
+            expected: "100ucosm".to_string(),
+            received: "50ucosm".to_string(),
+        }
+    );
+
+    // Joining with a different denom fails
+    let info = mock_info("player_2", &coins(100, "uatom"));
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info, 0),
+        pubkey: None,
+    };
+
+    let res = join_game(deps.as_mut(), env.clone(), info, msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::StakeMismatch {
+            expected: "100ucosm".to_string(),
+            received: "100uatom".to_string(),
+        }
+    );
+
+    // Joining with the matching stake
+    let info = mock_info("player_2", &coins(100, "ucosm"));
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info, 0),
+        pubkey: None,
+    };
+
+    join_game(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    reveal_seeds(&mut deps, &env, 0);
+
+    // Resigning hands both stakes to the opponent
+    let info = mock_info("player_2", &[]);
+    let msg = ResignMsg { game_id: 0 };
+
+    let res = resign(deps.as_mut(), env, info, msg).unwrap();
+
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "player_1".to_string(),
+            amount: coins(200, "ucosm"),
+        })]
+    );
+}
+
+#[test]
+fn refusing_funds_outside_stakes() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Creating and joining a wagered game
+    let info_1 = mock_info("player_1", &coins(100, "ucosm"));
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        ..Default::default()
+    };
+    create_game(deps.as_mut(), env.clone(), info_1, msg).unwrap();
+
+    let info_2 = mock_info("player_2", &coins(100, "ucosm"));
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_2, 0),
+        pubkey: None,
+    };
+    join_game(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+
+    // Only creating and joining take funds, anything else sent along is refused
+    let msgs = [
+        ExecuteMsg::RevealSeed(RevealSeedMsg {
+            game_id: 0,
+            secret: secret(&info_2, 0),
+        }),
+        ExecuteMsg::SubmitMove(SubmitMoveMsg {
+            game_id: 0,
+            position: Some(1),
+            coordinates: None,
+        }),
+        ExecuteMsg::OfferDraw(OfferDrawMsg { game_id: 0 }),
+        ExecuteMsg::Resign(ResignMsg { game_id: 0 }),
+        ExecuteMsg::ClaimTimeout(ClaimTimeoutMsg { game_id: 0 }),
+        ExecuteMsg::CancelGame(CancelGameMsg { game_id: 0 }),
+        ExecuteMsg::UpdateConfig(UpdateConfigMsg::default()),
+    ];
+    for msg in msgs {
+        let res = execute(deps.as_mut(), env.clone(), info_2.clone(), msg);
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::Payment(PaymentError::NonPayable {})
+        );
+    }
+
+    // the game is still waiting for the seeds
+    let game = load_game(&deps.storage, 0).unwrap();
+    assert_eq!(game.status, GameStatus::Revealing);
+}
+
+#[test]
+fn drawing_a_wagered_game() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Creating a game with a stake, sending more than one coin fails
+    let info = mock_info("player_1", &[coin(100, "ucosm"), coin(100, "uatom")]);
+    let msg = CreateGameMsg {
+        commitment: commitment(&info, 0),
+        ..Default::default()
+    };
+
+    let res = create_game(deps.as_mut(), env.clone(), info, msg);
+    assert_eq!(res.unwrap_err(), ContractError::InvalidStake {});
+
+    let info_1 = mock_info("player_1", &coins(100, "ucosm"));
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        ..Default::default()
+    };
+
+    create_game(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    // Joining with the matching stake
+    let info_2 = mock_info("player_2", &coins(100, "ucosm"));
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_2, 0),
+        pubkey: None,
+    };
+
+    join_game(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+    reveal_seeds(&mut deps, &env, 0);
+
+    // the stakes are escrowed, later messages are sent without funds
+    let (info_1, info_2) = (mock_info("player_1", &[]), mock_info("player_2", &[]));
+
+    // Playing the same moves as drawing_a_game, player_2 plays "X"
+    let moves = [
+        (&info_2, 1),
+        (&info_1, 2),
+        (&info_2, 3),
+        (&info_1, 4),
+        (&info_2, 5),
+        (&info_1, 7),
+        (&info_2, 6),
+        (&info_1, 9),
+    ];
+    for (info, position) in moves {
+        let msg = SubmitMoveMsg {
+            game_id: 0,
+            position: Some(position),
+            coordinates: None,
+        };
+        let res = submit_move(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert!(res.messages.is_empty());
+    }
+
+    // Submitting the last move refunds both stakes
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(8),
+        coordinates: None,
+    };
+    let res = submit_move(deps.as_mut(), env, info_2, msg).unwrap();
+
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(BankMsg::Send {
+                to_address: "player_2".to_string(),
+                amount: coins(100, "ucosm"),
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: "player_1".to_string(),
+                amount: coins(100, "ucosm"),
+            }),
+        ]
+    );
+}
+
+fn tic_tac_toe_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(execute, instantiate, query);
+    Box::new(contract)
+}
+
+fn cw20_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn cw20_balance(app: &App, token: &Addr, address: &str) -> Uint128 {
+    let res: BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(
+            token,
+            &Cw20QueryMsg::Balance {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+    res.balance
+}
+
+#[test]
+fn rejecting_untrusted_cw20_hooks() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    let instantiate_msg = InstantiateMsg {
+        cw20_tokens: Some(vec!["token".to_string()]),
+        ..Default::default()
+    };
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("instantiatoor", &[]),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Calling the hook directly, posing as a token on behalf of someone else, fails
+    let info = mock_info("player_3", &[]);
+    let msg = CreateGameMsg {
+        commitment: commitment(&info, 0),
+        ..Default::default()
+    };
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "player_1".to_string(),
+        amount: Uint128::new(100),
+        msg: to_binary(&ReceiveMsg::CreateGame(msg)).unwrap(),
+    });
+    let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::TokenNotAccepted {
+            token: "player_3".to_string()
+        }
+    );
+    let state = STATE.load(&deps.storage).unwrap();
+    assert_eq!(state.latest_game_id, 0);
+
+    // The accepted token can call it
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("token", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+
+    // The owner decides which tokens are accepted
+    let update_msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+        cw20_tokens: Some(vec![]),
+        ..Default::default()
+    });
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("instantiatoor", &[]),
+        update_msg,
+    )
+    .unwrap();
+    let err = execute(deps.as_mut(), env, mock_info("token", &[]), msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::TokenNotAccepted {
+            token: "token".to_string()
+        }
+    );
+}
+
+#[test]
+fn winning_a_cw20_wagered_game() {
+    let mut app = App::default();
+
+    // Instantiating a cw20 token with funded players
+    let cw20_code_id = app.store_code(cw20_contract());
+    let token = app
+        .instantiate_contract(
+            cw20_code_id,
+            Addr::unchecked("instantiatoor"),
+            &cw20_base::msg::InstantiateMsg {
+                name: "Tic Tac Token".to_string(),
+                symbol: "TTT".to_string(),
+                decimals: 6,
+                initial_balances: vec![
+                    Cw20Coin {
+                        address: "player_1".to_string(),
+                        amount: Uint128::new(1000),
+                    },
+                    Cw20Coin {
+                        address: "player_2".to_string(),
+                        amount: Uint128::new(1000),
+                    },
+                ],
+                mint: None,
+                marketing: None,
+            },
+            &[],
+            "token",
+            None,
+        )
+        .unwrap();
+
+    // Instantiating tic-tac-toe accepting the token for stakes
+    let code_id = app.store_code(tic_tac_toe_contract());
+    let contract = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked("instantiatoor"),
+            &InstantiateMsg {
+                cw20_tokens: Some(vec![token.to_string()]),
+                ..Default::default()
+            },
+            &[],
+            "tic-tac-toe",
+            None,
+        )
+        .unwrap();
+
+    let info_1 = mock_info("player_1", &[]);
+    let info_2 = mock_info("player_2", &[]);
+
+    // Creating a game by sending tokens to the contract
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        ..Default::default()
+    };
+    let msg = Cw20ExecuteMsg::Send {
+        contract: contract.to_string(),
+        amount: Uint128::new(100),
+        msg: to_binary(&ReceiveMsg::CreateGame(msg)).unwrap(),
+    };
+    app.execute_contract(Addr::unchecked("player_1"), token.clone(), &msg, &[])
+        .unwrap();
+
+    // Joining with a different amount fails
+    let msg = Cw20ExecuteMsg::Send {
+        contract: contract.to_string(),
+        amount: Uint128::new(50),
+        msg: to_binary(&ReceiveMsg::JoinGame(JoinGameMsg {
+            game_id: 0,
+            commitment: commitment(&info_2, 0),
+            pubkey: None,
+        }))
+        .unwrap(),
+    };
+    let err = app
+        .execute_contract(Addr::unchecked("player_2"), token.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().downcast_ref::<ContractError>(),
+        Some(&ContractError::StakeMismatch {
+            expected: format!("100{}", token),
+            received: format!("50{}", token),
+        })
+    );
+
+    // Joining with native funds fails
+    let msg = ExecuteMsg::JoinGame(JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_2, 0),
+        pubkey: None,
+    });
+    let err = app
+        .execute_contract(Addr::unchecked("player_2"), contract.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().downcast_ref::<ContractError>(),
+        Some(&ContractError::StakeMismatch {
+            expected: format!("100{}", token),
+            received: "nothing".to_string(),
+        })
+    );
+
+    // Joining with the matching amount
+    let msg = Cw20ExecuteMsg::Send {
+        contract: contract.to_string(),
+        amount: Uint128::new(100),
+        msg: to_binary(&ReceiveMsg::JoinGame(JoinGameMsg {
+            game_id: 0,
+            commitment: commitment(&info_2, 0),
+            pubkey: None,
+        }))
+        .unwrap(),
+    };
+    app.execute_contract(Addr::unchecked("player_2"), token.clone(), &msg, &[])
+        .unwrap();
+
+    // Revealing both seeds starts the game
+    for info in [&info_1, &info_2] {
+        let msg = ExecuteMsg::RevealSeed(RevealSeedMsg {
+            game_id: 0,
+            secret: secret(info, 0),
+        });
+        app.execute_contract(info.sender.clone(), contract.clone(), &msg, &[])
+            .unwrap();
+    }
+
+    assert_eq!(
+        cw20_balance(&app, &token, contract.as_str()),
+        Uint128::new(200)
+    );
+
+    let res: GameResponse = app
+        .wrap()
+        .query_wasm_smart(&contract, &QueryMsg::Game { game_id: 0 })
+        .unwrap();
+    let x = res.game.players[0].clone();
+    let o = res.game.players[1].clone();
+
+    // "X" completes the top row
+    for (player, position) in [(&x, 1), (&o, 4), (&x, 2), (&o, 5), (&x, 3)] {
+        let msg = ExecuteMsg::SubmitMove(SubmitMoveMsg {
+            game_id: 0,
+            position: Some(position),
+            coordinates: None,
+        });
+        app.execute_contract(player.clone(), contract.clone(), &msg, &[])
+            .unwrap();
+    }
+
+    // winner takes both stakes
+    assert_eq!(
+        cw20_balance(&app, &token, contract.as_str()),
+        Uint128::zero()
+    );
+    assert_eq!(cw20_balance(&app, &token, x.as_str()), Uint128::new(1100));
+    assert_eq!(cw20_balance(&app, &token, o.as_str()), Uint128::new(900));
+}
+
+#[test]
+fn claiming_a_timeout() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Creating a game with a ten block move timeout
+    let info_1 = mock_info("player_1", &[]);
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        move_timeout: Some(Duration::Height(10)),
+        ..Default::default()
+    };
+
+    create_game(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    // Joining an existing game, player_2 plays "X"
+    let info_2 = mock_info("player_2", &[]);
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_2, 0),
+        pubkey: None,
+    };
+
+    join_game(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+    reveal_seeds(&mut deps, &env, 0);
+
+    // Submitting a move five blocks later
+    env.block.height += 5;
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(5),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+
+    let game = load_game(&deps.storage, 0).unwrap();
+    assert_eq!(game.last_move_height, env.block.height);
+
+    // Claiming before player_1's clock expires fails
+    env.block.height += 9;
+    let msg = ClaimTimeoutMsg { game_id: 0 };
+
+    let res = claim_timeout(deps.as_mut(), env.clone(), info_2.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::TimeoutNotExpired {});
+
+    // Claiming on your own turn or as a non-player fails
+    env.block.height += 1;
+    for info in [&info_1, &mock_info("player_3", &[])] {
+        let msg = ClaimTimeoutMsg { game_id: 0 };
+
+        let res = claim_timeout(deps.as_mut(), env.clone(), info.clone(), msg);
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+    }
+
+    // Claiming once player_1's clock expired
+    let msg = ClaimTimeoutMsg { game_id: 0 };
+
+    let res = claim_timeout(deps.as_mut(), env.clone(), info_2, msg).unwrap();
+
+    let res_expected: Response = Response::new()
+        .add_attribute("action", "claim_timeout")
+        .add_attribute("game_id", "0")
+        .add_attribute("winner", "player_2")
+        .add_attribute("result", "timed_out")
+        .add_attribute("rating_delta", "player_2:+16")
+        .add_attribute("rating_delta", "player_1:-16");
+
+    let game = load_game(&deps.storage, 0).unwrap();
+
+    // attributes as expected & game completed & player_2 won
+    assert_eq!(res_expected, res);
+    assert_eq!(game.status, GameStatus::Completed);
+    assert_eq!(
+        game.result,
+        Some(GameResult::TimedOut {
+            by: Addr::unchecked("player_1")
+        })
+    );
+    assert_eq!(game.winner(), Some(Addr::unchecked("player_2")));
+
+    // a zero timeout is rejected
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 1),
+        move_timeout: Some(Duration::Time(0)),
+        ..Default::default()
+    };
+
+    let res = create_game(deps.as_mut(), env.clone(), info_1.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::InvalidTimeout {});
+
+    // so are timeouts that would overflow once added to the last move
+    for move_timeout in [Duration::Height(u64::MAX), Duration::Time(u64::MAX)] {
+        let msg = CreateGameMsg {
+            commitment: commitment(&info_1, 1),
+            move_timeout: Some(move_timeout),
+            ..Default::default()
+        };
+
+        let res = create_game(deps.as_mut(), env.clone(), info_1.clone(), msg);
+        assert_eq!(res.unwrap_err(), ContractError::TimeoutTooLong {});
+    }
+
+    // up to 30 days is fine
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 1),
+        move_timeout: Some(Duration::Time(30 * 24 * 60 * 60)),
+        ..Default::default()
+    };
+    create_game(deps.as_mut(), env, info_1, msg).unwrap();
+}
+
+#[test]
+fn refusing_to_reveal() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Creating and joining two wagered games with a ten block timeout
+    let info_1 = mock_info("player_1", &coins(100, "ucosm"));
+    let info_2 = mock_info("player_2", &coins(100, "ucosm"));
+    for game_id in 0..2 {
+        let msg = CreateGameMsg {
+            commitment: commitment(&info_1, game_id),
+            move_timeout: Some(Duration::Height(10)),
+            ..Default::default()
+        };
+        create_game(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+        let msg = JoinGameMsg {
+            game_id,
+            commitment: commitment(&info_2, game_id),
+            pubkey: None,
+        };
+        join_game(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+    }
+
+    // the stakes are escrowed, later messages are sent without funds
+    let (info_1, info_2) = (mock_info("player_1", &[]), mock_info("player_2", &[]));
+
+    // only player_1 reveals in the first game
+    let msg = RevealSeedMsg {
+        game_id: 0,
+        secret: secret(&info_1, 0),
+    };
+    reveal_seed(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    // Claiming before the timeout expires fails
+    env.block.height += 9;
+    let msg = ClaimTimeoutMsg { game_id: 0 };
+
+    let res = claim_timeout(deps.as_mut(), env.clone(), info_1.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::TimeoutNotExpired {});
+
+    // Claiming as the player who didn't reveal fails
+    env.block.height += 1;
+    let msg = ClaimTimeoutMsg { game_id: 0 };
+
+    let res = claim_timeout(deps.as_mut(), env.clone(), info_2.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    // Claiming as the player who revealed wins both stakes
+    let msg = ClaimTimeoutMsg { game_id: 0 };
+
+    let res = claim_timeout(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    let game = load_game(&deps.storage, 0).unwrap();
+
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "player_1".to_string(),
+            amount: coins(200, "ucosm"),
+        })]
+    );
+    assert_eq!(game.status, GameStatus::Completed);
+    assert_eq!(
+        game.result,
+        Some(GameResult::TimedOut {
+            by: Addr::unchecked("player_2")
+        })
+    );
+
+    // Claiming when nobody revealed cancels the game and refunds both players
+    let msg = ClaimTimeoutMsg { game_id: 1 };
+
+    let res = claim_timeout(deps.as_mut(), env, info_2, msg).unwrap();
+
+    let game = load_game(&deps.storage, 1).unwrap();
+
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(BankMsg::Send {
+                to_address: "player_1".to_string(),
+                amount: coins(100, "ucosm"),
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: "player_2".to_string(),
+                amount: coins(100, "ucosm"),
+            }),
+        ]
+    );
+    assert_eq!(game.status, GameStatus::Cancelled);
+    assert_eq!(game.result, Some(GameResult::Cancelled));
+}
+
+// mock api for a chain whose addresses are the hex encoded ripemd160 of the sha256 of the public
+// key, so tests can derive senders from known keys
+#[derive(Default)]
+struct HexApi(MockApi);
+
+impl HexApi {
+    fn address(pubkey: &[u8]) -> String {
+        let hash = Ripemd160::digest(Sha256::digest(pubkey));
+        hash.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+}
+
+impl Api for HexApi {
+    fn addr_validate(&self, human: &str) -> StdResult<Addr> {
+        self.0.addr_validate(human)
+    }
+
+    fn addr_canonicalize(&self, human: &str) -> StdResult<CanonicalAddr> {
+        if human.len() != 40 {
+            return self.0.addr_canonicalize(human);
+        }
+        (0..human.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&human[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map(CanonicalAddr::from)
+            .map_err(|err| StdError::generic_err(err.to_string()))
+    }
+
+    fn addr_humanize(&self, canonical: &CanonicalAddr) -> StdResult<Addr> {
+        self.0.addr_humanize(canonical)
+    }
+
+    fn secp256k1_verify(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError> {
+        self.0.secp256k1_verify(message_hash, signature, public_key)
+    }
+
+    fn secp256k1_recover_pubkey(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        recovery_param: u8,
+    ) -> Result<Vec<u8>, RecoverPubkeyError> {
+        self.0
+            .secp256k1_recover_pubkey(message_hash, signature, recovery_param)
+    }
+
+    fn ed25519_verify(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError> {
+        self.0.ed25519_verify(message, signature, public_key)
+    }
+
+    fn ed25519_batch_verify(
+        &self,
+        messages: &[&[u8]],
+        signatures: &[&[u8]],
+        public_keys: &[&[u8]],
+    ) -> Result<bool, VerificationError> {
+        self.0
+            .ed25519_batch_verify(messages, signatures, public_keys)
+    }
+
+    fn debug(&self, message: &str) {
+        self.0.debug(message)
+    }
+}
+
+#[test]
+fn assigning_roles_from_public_keys() {
+    let mut deps = OwnedDeps {
+        storage: MockStorage::default(),
+        api: HexApi::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    };
+    let env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    let pubkey_1 = Binary::from([[0x02].as_slice(), &[0x11; 32]].concat());
+    let pubkey_2 = Binary::from([[0x03].as_slice(), &[0x22; 32]].concat());
+    let info_1 = mock_info(&HexApi::address(&pubkey_1), &[]);
+    let info_2 = mock_info(&HexApi::address(&pubkey_2), &[]);
+
+    // Creating a game with someone else's key fails
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        pubkey: Some(pubkey_2.clone()),
+        ..Default::default()
+    };
+
+    let res = create_game(deps.as_mut(), env.clone(), info_1.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::PubkeyMismatch {});
+
+    // Creating a game with an uncompressed key fails
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        pubkey: Some(Binary::from([0x04; 65])),
+        ..Default::default()
+    };
+
+    let res = create_game(deps.as_mut(), env.clone(), info_1.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::InvalidPubkey {});
+
+    // Creating a game with the creator's key
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        pubkey: Some(pubkey_1.clone()),
+        ..Default::default()
+    };
+
+    create_game(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    // Joining with the joiner's key and revealing both seeds
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_2, 0),
+        pubkey: Some(pubkey_2.clone()),
+    };
+
+    join_game(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+    reveal_seeds(&mut deps, &env, 0);
+
+    let game = load_game(&deps.storage, 0).unwrap();
+    assert_eq!(game.seeds[0].pubkey, Some(pubkey_1.clone()));
+    assert_eq!(game.seeds[1].pubkey, Some(pubkey_2.clone()));
+
+    // the hash of both keys followed by both secrets decides roles, the joiner plays "X" when its
+    // first bit is 0
+    let hash = Sha256::new()
+        .chain_update(&pubkey_1)
+        .chain_update(&pubkey_2)
+        .chain_update(secret(&info_1, 0))
+        .chain_update(secret(&info_2, 0))
+        .finalize();
+    let x = match hash[0] >> 7 {
+        0 => &info_2.sender,
+        _ => &info_1.sender,
+    };
+
+    assert_eq!(game.status, GameStatus::InProgress);
+    assert_eq!(&game.players[0], x);
+    assert_eq!(game.next_turn.as_ref(), Some(x));
+}
+
+// drand quicknet, https://api.drand.sh/52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971/info
+const QUICKNET_PUBKEY: &str = "83cf0f2896adee7eb8b5f01fcad3912212c437e0073e911fb90022d3e760183c8c4b450b6a0a6c3ac6a5776a2d1064510d1fec758c921cc22b0e17e63aaf4bcb5ed66304de9cf809bd274ca73bab4af5a6e9c76a4bc09e76eae8991ef5ece45a";
+const QUICKNET_GENESIS_TIME: u64 = 1692803367;
+const QUICKNET_PERIOD: u64 = 3;
+
+// recorded beacon, https://api.drand.sh/52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971/public/123
+const BEACON_ROUND: u64 = 123;
+const BEACON_SIGNATURE: &str = "b75c69d0b72a5d906e854e808ba7e2accb1542ac355ae486d591aa9d43765482e26cd02df835d3546d23c4b13e0dfc92";
+
+fn from_hex(hex: &str) -> Binary {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect::<Vec<u8>>()
+        .into()
+}
+
+#[test]
+fn assigning_roles_from_a_drand_beacon() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+
+    // Instantiating without drand, games can't wait for beacons
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    let info_1 = mock_info("player_1", &[]);
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        use_beacon: Some(true),
+        ..Default::default()
+    };
+
+    let res = create_game(deps.as_mut(), env.clone(), info_1.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::BeaconNotConfigured {});
+
+    // Instantiating with quicknet
+    let drand = DrandConfig {
+        pubkey: from_hex(QUICKNET_PUBKEY),
+        genesis_time: QUICKNET_GENESIS_TIME,
+        period: QUICKNET_PERIOD,
+    };
+    let instantiate_msg = InstantiateMsg {
+        drand: Some(drand.clone()),
+        ..Default::default()
+    };
+
+    instantiate(deps.as_mut(), env.clone(), admin_info, instantiate_msg).unwrap();
+
+    let msg = QueryMsg::Config {};
+    let res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(res.drand, Some(drand));
+
+    // Creating a game waiting for a beacon
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        use_beacon: Some(true),
+        ..Default::default()
+    };
+
+    create_game(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    // Joining right as round 111 is emitted, round 112 is the first one nobody knows yet
+    env.block.time = Timestamp::from_seconds(QUICKNET_GENESIS_TIME + 110 * QUICKNET_PERIOD);
+    let info_2 = mock_info("player_2", &[]);
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_2, 0),
+        pubkey: None,
+    };
+
+    let res = join_game(deps.as_mut(), env.clone(), info_2, msg).unwrap();
+    assert!(res.attributes.contains(&attr("beacon_round", "112")));
+
+    // Revealing both seeds isn't enough to start the game
+    reveal_seeds(&mut deps, &env, 0);
+
+    let game = load_game(&deps.storage, 0).unwrap();
+    assert_eq!(game.status, GameStatus::Revealing);
+
+    // Submitting a round from before the join fails
+    let msg = SubmitBeaconMsg {
+        game_id: 0,
+        round: 111,
+        signature: from_hex(BEACON_SIGNATURE),
+    };
+    let res = submit_beacon(deps.as_mut(), env.clone(), info_1.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::WrongBeaconRound { expected: 112 }
+    );
+
+    // So does a later round, even with a valid signature, as it could have been picked
+    let msg = SubmitBeaconMsg {
+        game_id: 0,
+        round: BEACON_ROUND,
+        signature: from_hex(BEACON_SIGNATURE),
+    };
+    let res = submit_beacon(deps.as_mut(), env.clone(), info_1.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::WrongBeaconRound { expected: 112 }
+    );
+
+    // Submitting a signature that doesn't belong to the round fails
+    let msg = SubmitBeaconMsg {
+        game_id: 0,
+        round: 112,
+        signature: from_hex(BEACON_SIGNATURE),
+    };
+    let res = submit_beacon(deps.as_mut(), env.clone(), info_1.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::InvalidBeacon {});
+
+    // Joining another game right before the recorded beacon's round
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 1),
+        use_beacon: Some(true),
+        ..Default::default()
+    };
+    create_game(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    env.block.time =
+        Timestamp::from_seconds(QUICKNET_GENESIS_TIME + (BEACON_ROUND - 2) * QUICKNET_PERIOD);
+    let info_2 = mock_info("player_2", &[]);
+    let msg = JoinGameMsg {
+        game_id: 1,
+        commitment: commitment(&info_2, 1),
+        pubkey: None,
+    };
+    let res = join_game(deps.as_mut(), env.clone(), info_2, msg).unwrap();
+    assert!(res.attributes.contains(&attr("beacon_round", "123")));
+    reveal_seeds(&mut deps, &env, 1);
+
+    // Submitting the recorded beacon, anyone can do it
+    let msg = SubmitBeaconMsg {
+        game_id: 1,
+        round: BEACON_ROUND,
+        signature: from_hex(BEACON_SIGNATURE),
+    };
+    let res = submit_beacon(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap();
+
+    let game = load_game(&deps.storage, 1).unwrap();
+
+    // game started & the beacon's randomness is the sha256 of its signature
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "submit_beacon"),
+            attr("game_id", "1"),
+            attr("round", "123"),
+            attr("X", game.players[0].clone()),
+        ]
+    );
+    assert_eq!(game.status, GameStatus::InProgress);
+    assert_eq!(
+        game.randomness,
+        Some(Binary::from(
+            Sha256::digest(from_hex(BEACON_SIGNATURE)).as_slice()
+        ))
+    );
+
+    // Submitting another beacon fails
+    let msg = SubmitBeaconMsg {
+        game_id: 1,
+        round: BEACON_ROUND,
+        signature: from_hex(BEACON_SIGNATURE),
+    };
+    let res = submit_beacon(deps.as_mut(), env, info_1, msg);
+    assert_eq!(res.unwrap_err(), ContractError::GameNotRevealing {});
+}
+
+#[test]
+fn cancelling_an_open_game() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Creating a game with a stake
+    let info = mock_info("player_1", &coins(100, "ucosm"));
+    let msg = CreateGameMsg {
+        commitment: commitment(&info, 0),
+        ..Default::default()
+    };
+
+    create_game(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // Cancelling someone else's game fails
+    let info = mock_info("player_2", &[]);
+    let msg = CancelGameMsg { game_id: 0 };
+
+    let res = cancel_game(deps.as_mut(), env.clone(), info, msg);
+    assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+    // Cancelling as the creator refunds the stake
+    let info = mock_info("player_1", &[]);
+    let msg = CancelGameMsg { game_id: 0 };
+
+    let res = cancel_game(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let res_expected: Response = Response::new()
+        .add_message(BankMsg::Send {
+            to_address: "player_1".to_string(),
+            amount: coins(100, "ucosm"),
+        })
+        .add_attribute("action", "cancel_game")
+        .add_attribute("game_id", "0")
+        .add_attribute("result", "cancelled");
+
+    let game = load_game(&deps.storage, 0).unwrap();
+
+    // attributes and refund as expected & game cancelled
+    assert_eq!(res_expected, res);
+    assert_eq!(game.status, GameStatus::Cancelled);
+    assert_eq!(game.result, Some(GameResult::Cancelled));
+
+    // Cancelling or joining a cancelled game fails
+    let msg = CancelGameMsg { game_id: 0 };
+
+    let res = cancel_game(deps.as_mut(), env.clone(), info, msg);
+    assert_eq!(res.unwrap_err(), ContractError::GameNotOpen {});
+
+    let info = mock_info("player_2", &coins(100, "ucosm"));
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info, 0),
+        pubkey: None,
+    };
+
+    let res = join_game(deps.as_mut(), env, info, msg);
+    assert_eq!(res.unwrap_err(), ContractError::CantJoinGame {});
+}
+
+#[test]
+fn expiring_an_open_game() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+
+    // Instantiating with open games expiring after ten blocks
+    let instantiate_msg = InstantiateMsg {
+        open_game_expiry: Some(10),
+        ..Default::default()
+    };
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Creating a game
+    let info = mock_info("player_1", &[]);
+    let msg = CreateGameMsg {
+        commitment: commitment(&info, 0),
+        ..Default::default()
+    };
+
+    create_game(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // Ten blocks later the game is gone from the open games
+    env.block.height += 10;
+
+    let msg = QueryMsg::OpenGames {
+        start_after: None,
+        limit: None,
+    };
+    let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let list: ListGamesResponse = from_binary(&res).unwrap();
+    assert!(list.games.is_empty());
+
+    // Joining an expired game fails
+    let info = mock_info("player_2", &[]);
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info, 0),
+        pubkey: None,
+    };
+
+    let res = join_game(deps.as_mut(), env.clone(), info.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::GameExpired {});
+
+    // Anyone can cancel an expired game
+    let msg = CancelGameMsg { game_id: 0 };
+
+    cancel_game(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let game = load_game(&deps.storage, 0).unwrap();
+    assert_eq!(game.status, GameStatus::Cancelled);
+
+    // A page reads at most 100 open games, so a pile of expired ones hides the games after them
+    let info = mock_info("player_1", &[]);
+    for game_id in 1..=100 {
+        let msg = CreateGameMsg {
+            commitment: commitment(&info, game_id),
+            ..Default::default()
+        };
+        create_game(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    }
+    env.block.height += 10;
+
+    let msg = CreateGameMsg {
+        commitment: commitment(&info, 101),
+        ..Default::default()
+    };
+    create_game(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = QueryMsg::OpenGames {
+        start_after: None,
+        limit: None,
+    };
+    let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let list: ListGamesResponse = from_binary(&res).unwrap();
+    assert!(list.games.is_empty());
+
+    // paginating past them finds the open game
+    let msg = QueryMsg::OpenGames {
+        start_after: Some(100),
+        limit: None,
+    };
+    let res = query(deps.as_ref(), env, msg).unwrap();
+    let list: ListGamesResponse = from_binary(&res).unwrap();
+    assert_eq!(list.games.len(), 1);
+    assert_eq!(list.games[0].id, 101);
+}
+
+#[test]
+fn agreeing_a_draw() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Creating a game with a stake
+    let info_1 = mock_info("player_1", &coins(100, "ucosm"));
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        ..Default::default()
+    };
+
+    create_game(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    // Joining an existing game, player_2 plays "X"
+    let info_2 = mock_info("player_2", &coins(100, "ucosm"));
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_2, 0),
+        pubkey: None,
+    };
+
+    join_game(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+    reveal_seeds(&mut deps, &env, 0);
+
+    // the stakes are escrowed, later messages are sent without funds
+    let (info_1, info_2) = (mock_info("player_1", &[]), mock_info("player_2", &[]));
+
+    // Accepting a draw nobody offered fails
+    let msg = AcceptDrawMsg { game_id: 0 };
+
+    let res = accept_draw(deps.as_mut(), env.clone(), info_1.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::NoDrawOffer {});
+
+    // Offering a draw before the first move
+    let msg = OfferDrawMsg { game_id: 0 };
+
+    let res = offer_draw(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    let res_expected: Response = Response::new()
+        .add_attribute("action", "offer_draw")
+        .add_attribute("game_id", "0")
+        .add_attribute("offered_by", "player_1");
+
+    assert_eq!(res_expected, res);
+
+    // Offering twice or accepting your own offer fails
+    let msg = OfferDrawMsg { game_id: 0 };
+
+    let res = offer_draw(deps.as_mut(), env.clone(), info_2.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::DrawAlreadyOffered {});
+
+    let msg = AcceptDrawMsg { game_id: 0 };
+
+    let res = accept_draw(deps.as_mut(), env.clone(), info_1, msg);
+    assert_eq!(res.unwrap_err(), ContractError::NoDrawOffer {});
+
+    // Accepting the offer refunds both stakes
+    let msg = AcceptDrawMsg { game_id: 0 };
+
+    let res = accept_draw(deps.as_mut(), env, info_2, msg).unwrap();
+
+    let res_expected: Response = Response::new()
+        .add_message(BankMsg::Send {
+            to_address: "player_2".to_string(),
+            amount: coins(100, "ucosm"),
+        })
+        .add_message(BankMsg::Send {
+            to_address: "player_1".to_string(),
+            amount: coins(100, "ucosm"),
+        })
+        .add_attribute("action", "accept_draw")
+        .add_attribute("game_id", "0")
+        .add_attribute("result", "draw")
+        .add_attribute("rating_delta", "player_2:+0")
+        .add_attribute("rating_delta", "player_1:+0");
+
+    let game = load_game(&deps.storage, 0).unwrap();
+
+    // attributes and refunds as expected & game completed as a draw
+    assert_eq!(res_expected, res);
+    assert_eq!(game.status, GameStatus::Completed);
+    assert_eq!(game.result, Some(GameResult::Draw));
+    assert_eq!(game.draw_offer, None);
+}
+
+#[test]
+fn withdrawing_a_draw_offer() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Creating a game
+    let info_1 = mock_info("player_1", &[]);
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        ..Default::default()
+    };
+
+    create_game(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    // Joining an existing game, player_2 plays "X"
+    let info_2 = mock_info("player_2", &[]);
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_2, 0),
+        pubkey: None,
+    };
+
+    join_game(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+    reveal_seeds(&mut deps, &env, 0);
+
+    // Offering a draw
+    let msg = OfferDrawMsg { game_id: 0 };
+
+    offer_draw(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    // Submitting a move instead of answering withdraws the offer
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(5),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+
+    let game = load_game(&deps.storage, 0).unwrap();
+    assert_eq!(game.draw_offer, None);
+
+    // Offering a draw again, the offerer moving keeps it pending
+    let msg = OfferDrawMsg { game_id: 0 };
+
+    offer_draw(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(1),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env.clone(), info_1, msg).unwrap();
+
+    let game = load_game(&deps.storage, 0).unwrap();
+    assert_eq!(game.draw_offer, Some(Addr::unchecked("player_1")));
+
+    // Declining the offer, the game goes on
+    let msg = DeclineDrawMsg { game_id: 0 };
+
+    let res = decline_draw(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+
+    let res_expected: Response = Response::new()
+        .add_attribute("action", "decline_draw")
+        .add_attribute("game_id", "0");
+
+    let game = load_game(&deps.storage, 0).unwrap();
+
+    assert_eq!(res_expected, res);
+    assert_eq!(game.status, GameStatus::InProgress);
+    assert_eq!(game.draw_offer, None);
+
+    // Answering a declined offer fails
+    let msg = AcceptDrawMsg { game_id: 0 };
+
+    let res = accept_draw(deps.as_mut(), env, info_2, msg);
+    assert_eq!(res.unwrap_err(), ContractError::NoDrawOffer {});
+}
+
+#[test]
+fn tracking_player_stats() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Playing three games, the second player of each one resigns
+    let pairings = [
+        ("player_1", "player_2"),
+        ("player_1", "player_3"),
+        ("player_2", "player_3"),
+    ];
+    for (game_id, (creator, joiner)) in pairings.into_iter().enumerate() {
+        let info = mock_info(creator, &[]);
+        let msg = CreateGameMsg {
+            commitment: commitment(&info, game_id as u64),
+            ..Default::default()
+        };
+        create_game(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let info = mock_info(joiner, &[]);
+        let msg = JoinGameMsg {
+            game_id: game_id as u64,
+            commitment: commitment(&info, game_id as u64),
+            pubkey: None,
+        };
+        join_game(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        reveal_seeds(&mut deps, &env, game_id as u64);
+
+        let msg = ResignMsg {
+            game_id: game_id as u64,
+        };
+        resign(deps.as_mut(), env.clone(), info, msg).unwrap();
+    }
+
+    // Querying a single player's stats
+    let msg = QueryMsg::PlayerStats {
+        address: "player_2".to_string(),
+    };
+    let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: PlayerStatsResponse = from_binary(&res).unwrap();
+
+    assert_eq!(
+        res.stats,
+        PlayerStats {
+            wins: 1,
+            losses: 1,
+            draws: 0,
+            resignations: 1,
+            games_played: 2,
+        }
+    );
+
+    // Players that never played have empty stats
+    let msg = QueryMsg::PlayerStats {
+        address: "player_4".to_string(),
+    };
+    let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: PlayerStatsResponse = from_binary(&res).unwrap();
+
+    assert_eq!(res.stats, PlayerStats::default());
+
+    // Leaderboard ranks players by wins
+    let msg = QueryMsg::Leaderboard {
+        start_after: None,
+        limit: None,
+    };
+    let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: LeaderboardResponse = from_binary(&res).unwrap();
+    let ranking: Vec<(String, u64)> = res
+        .players
+        .iter()
+        .map(|player| (player.address.to_string(), player.stats.wins))
+        .collect();
+
+    assert_eq!(
+        ranking,
+        vec![
+            ("player_1".to_string(), 2),
+            ("player_2".to_string(), 1),
+            ("player_3".to_string(), 0),
+        ]
+    );
+
+    // Paginating over the leaderboard
+    let msg = QueryMsg::Leaderboard {
+        start_after: Some("player_1".to_string()),
+        limit: Some(1),
+    };
+    let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: LeaderboardResponse = from_binary(&res).unwrap();
+
+    assert_eq!(res.players.len(), 1);
+    assert_eq!(res.players[0].address, Addr::unchecked("player_2"));
+
+    // Paginating after a player without stats starts over from the top
+    let msg = QueryMsg::Leaderboard {
+        start_after: Some("stranger".to_string()),
+        limit: Some(1),
+    };
+    let res = query(deps.as_ref(), env, msg).unwrap();
+    let res: LeaderboardResponse = from_binary(&res).unwrap();
+
+    assert_eq!(res.players.len(), 1);
+    assert_eq!(res.players[0].address, Addr::unchecked("player_1"));
+}
+
+#[test]
+fn computing_elo_deltas() {
+    // evenly matched players
+    assert_eq!(elo_delta(1200, 1200, 2, 32), 16);
+    assert_eq!(elo_delta(1200, 1200, 1, 32), 0);
+    assert_eq!(elo_delta(1200, 1200, 0, 32), -16);
+
+    // 400 points apart, the expected score of the lower rated player is 1/11
+    assert_eq!(elo_delta(1200, 1600, 2, 32), 29);
+    assert_eq!(elo_delta(1600, 1200, 2, 32), 3);
+    assert_eq!(elo_delta(1600, 1200, 1, 32), -13);
+
+    // differences are capped at 800 points
+    assert_eq!(elo_delta(0, 2000, 2, 32), 32);
+    assert_eq!(elo_delta(2000, 0, 0, 32), -32);
+
+    // ratings never go below zero
+    assert_eq!(apply_delta(10, -16), 0);
+}
+
+#[test]
+fn rating_players() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Instantiating with custom Elo settings
+    let instantiate_msg = InstantiateMsg {
+        initial_rating: Some(1500),
+        k_factor: Some(20),
+        ..Default::default()
+    };
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Playing two games, the joiner resigns the first one and the creator the second one
+    let info_1 = mock_info("player_1", &[]);
+    let info_2 = mock_info("player_2", &[]);
+    for (game_id, resigner) in [(0, &info_2), (1, &info_1)] {
+        create_game(
+            deps.as_mut(),
+            env.clone(),
+            info_1.clone(),
+            CreateGameMsg {
+                commitment: commitment(&info_1, game_id),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let msg = JoinGameMsg {
+            game_id,
+            commitment: commitment(&info_2, game_id),
+            pubkey: None,
+        };
+        join_game(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+        reveal_seeds(&mut deps, &env, game_id);
+
+        let msg = ResignMsg { game_id };
+        resign(deps.as_mut(), env.clone(), resigner.clone(), msg).unwrap();
+    }
+
+    // player_1 went up to 1510 then lost 11 points to the lower rated player_2
+    let ratings: Vec<u32> = ["player_1", "player_2", "player_3"]
+        .into_iter()
+        .map(|address| {
+            let msg = QueryMsg::Rating {
+                address: address.to_string(),
+            };
+            let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+            let res: RatingResponse = from_binary(&res).unwrap();
+            res.rating
+        })
+        .collect();
+
+    // unrated players get the initial rating
+    assert_eq!(ratings, vec![1499, 1501, 1500]);
+}
+
+#[test]
+fn matchmaking_through_the_queue() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Entering the queue with an empty rating range or with funds fails
+    let info_1 = mock_info("player_1", &[]);
+    let msg = EnterQueueMsg {
+        commitment: commitment(&info_1, 0),
+        pubkey: None,
+        min_rating: 1300,
+        max_rating: 1000,
+    };
+
+    let res = enter_queue(deps.as_mut(), env.clone(), info_1.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::InvalidRatingRange {});
+
+    let msg = EnterQueueMsg {
+        commitment: commitment(&info_1, 0),
+        pubkey: None,
+        min_rating: 1000,
+        max_rating: 1300,
+    };
+
+    let res = enter_queue(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("player_1", &coins(100, "ucosm")),
+        msg,
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::Payment(PaymentError::NonPayable {})
+    );
+
+    // Entering an empty queue parks the player
+    let msg = EnterQueueMsg {
+        commitment: commitment(&info_1, 0),
+        pubkey: None,
+        min_rating: 1000,
+        max_rating: 1300,
+    };
+
+    let res = enter_queue(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    let res_expected: Response = Response::new()
+        .add_attribute("action", "enter_queue")
+        .add_attribute("player", "player_1")
+        .add_attribute("rating", "1200");
+
+    assert_eq!(res_expected, res);
+
+    let msg = QueryMsg::QueueStatus {
+        address: "player_1".to_string(),
+    };
+    let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: QueueStatusResponse = from_binary(&res).unwrap();
+
+    assert_eq!(
+        res.entry,
+        Some(QueueEntry {
+            player: Addr::unchecked("player_1"),
+            commitment: commitment(&info_1, 0),
+            pubkey: None,
+            rating: 1200,
+            min_rating: 1000,
+            max_rating: 1300,
+        })
+    );
+
+    // Entering twice fails
+    let msg = EnterQueueMsg {
+        commitment: commitment(&info_1, 0),
+        pubkey: None,
+        min_rating: 1000,
+        max_rating: 1300,
+    };
+
+    let res = enter_queue(deps.as_mut(), env.clone(), info_1.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::AlreadyQueued {});
+
+    // player_0 doesn't accept player_1's rating, so both keep waiting
+    let info_0 = mock_info("player_0", &[]);
+    let msg = EnterQueueMsg {
+        commitment: commitment(&info_0, 0),
+        pubkey: None,
+        min_rating: 1300,
+        max_rating: 1500,
+    };
+
+    enter_queue(deps.as_mut(), env.clone(), info_0.clone(), msg).unwrap();
+
+    // player_2 is compatible with player_1 and starts a game right away
+    let info_2 = mock_info("player_2", &[]);
+    let msg = EnterQueueMsg {
+        commitment: commitment(&info_2, 0),
+        pubkey: None,
+        min_rating: 1100,
+        max_rating: 1250,
+    };
+
+    let res = enter_queue(deps.as_mut(), env.clone(), info_2, msg).unwrap();
+    assert_eq!(res.attributes[3..], [attr("game_id", "0")]);
+
+    let game = load_game(&deps.storage, 0).unwrap();
+    assert_eq!(game.status, GameStatus::Revealing);
+
+    // Revealing both seeds starts the game
+    reveal_seeds(&mut deps, &env, 0);
+
+    let game = load_game(&deps.storage, 0).unwrap();
+
+    // game started between player_1 and player_2 & player_1 left the queue
+    assert_eq!(game.status, GameStatus::InProgress);
+    assert!(game.players.contains(&Addr::unchecked("player_1")));
+    assert!(game.players.contains(&Addr::unchecked("player_2")));
+
+    let msg = QueryMsg::QueueStatus {
+        address: "player_1".to_string(),
+    };
+    let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: QueueStatusResponse = from_binary(&res).unwrap();
+    assert_eq!(res.entry, None);
+
+    // Queueing again with the commitment revealed in that game fails
+    let msg = EnterQueueMsg {
+        commitment: commitment(&info_1, 0),
+        pubkey: None,
+        min_rating: 1000,
+        max_rating: 1300,
+    };
+
+    let res = enter_queue(deps.as_mut(), env.clone(), info_1.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::CommitmentUsed {});
+
+    // player_1 queues again and then wins their game, the entry follows the new rating
+    let msg = EnterQueueMsg {
+        commitment: commitment(&info_1, 1),
+        pubkey: None,
+        min_rating: 1000,
+        max_rating: 1300,
+    };
+
+    enter_queue(deps.as_mut(), env.clone(), info_1, msg).unwrap();
+
+    let msg = ResignMsg { game_id: 0 };
+    resign(deps.as_mut(), env.clone(), mock_info("player_2", &[]), msg).unwrap();
+
+    let msg = QueryMsg::QueueStatus {
+        address: "player_1".to_string(),
+    };
+    let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: QueueStatusResponse = from_binary(&res).unwrap();
+    assert_eq!(res.entry.unwrap().rating, 1216);
+
+    // player_3 only accepts player_1's old rating, so both keep waiting
+    let info_3 = mock_info("player_3", &[]);
+    let msg = EnterQueueMsg {
+        commitment: commitment(&info_3, 0),
+        pubkey: None,
+        min_rating: 1100,
+        max_rating: 1210,
+    };
+
+    let res = enter_queue(deps.as_mut(), env.clone(), info_3, msg).unwrap();
+    assert_eq!(res.attributes.len(), 3);
+    assert!(queue().has(&deps.storage, &Addr::unchecked("player_1")));
+
+    // Leaving the queue
+    let msg = LeaveQueueMsg {};
+
+    let res = leave_queue(deps.as_mut(), env.clone(), info_0.clone(), msg).unwrap();
+
+    let res_expected: Response = Response::new()
+        .add_attribute("action", "leave_queue")
+        .add_attribute("player", "player_0");
+
+    assert_eq!(res_expected, res);
+
+    // Leaving twice fails
+    let msg = LeaveQueueMsg {};
+
+    let res = leave_queue(deps.as_mut(), env, info_0, msg);
+    assert_eq!(res.unwrap_err(), ContractError::NotQueued {});
+}
+
+#[test]
+fn winning_a_gomoku_game() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Creating a game with a win length longer than the board fails
+    let info_1 = mock_info("player_1", &[]);
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        rows: Some(4),
+        cols: Some(4),
+        win_length: Some(5),
+        ..Default::default()
+    };
+
+    let res = create_game(deps.as_mut(), env.clone(), info_1.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InvalidBoard { min: 3, max: 19 }
+    );
+
+    // Creating a 15x15 five-in-a-row game
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        rows: Some(15),
+        cols: Some(15),
+        win_length: Some(5),
+        ..Default::default()
+    };
+
+    create_game(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    let game = load_game(&deps.storage, 0).unwrap();
+    assert_eq!(game.moves.len(), 225);
+
+    // Joining an existing game, player_2 plays "X"
+    let info_2 = mock_info("player_2", &[]);
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_2, 0),
+        pubkey: None,
+    };
+
+    join_game(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+    reveal_seeds(&mut deps, &env, 0);
+
+    // Submitting a move outside the board fails and reports its size
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(226),
+        coordinates: None,
+    };
+    let res = submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InvalidPosition {
+            position: "226".to_string(),
+            max: 225,
+        }
+    );
+
+    // "X" fills four cells of the first column while "O" plays along the first row
+    let moves = [
+        (&info_2, 1),
+        (&info_1, 2),
+        (&info_2, 16),
+        (&info_1, 3),
+        (&info_2, 31),
+        (&info_1, 4),
+        (&info_2, 46),
+        (&info_1, 5),
+    ];
+    for (info, position) in moves {
+        let msg = SubmitMoveMsg {
+            game_id: 0,
+            position: Some(position),
+            coordinates: None,
+        };
+        submit_move(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    }
+
+    // four in a row isn't enough
+    let game = load_game(&deps.storage, 0).unwrap();
+    assert_eq!(game.status, GameStatus::InProgress);
+
+    // Submitting the fifth cell of the column
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(61),
+        coordinates: None,
+    };
+    submit_move(deps.as_mut(), env, info_2, msg).unwrap();
+
+    let game = load_game(&deps.storage, 0).unwrap();
+
+    // game completed & player_2 wins as "X"
+    assert_eq!(game.status, GameStatus::Completed);
+    assert_eq!(game.result, Some(GameResult::XWins));
+    assert_eq!(game.winner(), Some(Addr::unchecked("player_2")));
+}
+
+#[test]
+fn winning_an_ultimate_game() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Creating an ultimate game with a custom board size fails
+    let info_1 = mock_info("player_1", &[]);
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        variant: Some(GameVariant::Ultimate),
+        rows: Some(4),
+        ..Default::default()
+    };
+
+    let res = create_game(deps.as_mut(), env.clone(), info_1.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InvalidBoard { min: 3, max: 19 }
+    );
+
+    // Creating an ultimate game
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        variant: Some(GameVariant::Ultimate),
+        ..Default::default()
+    };
+
+    create_game(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    let game = load_game(&deps.storage, 0).unwrap();
+    assert_eq!(game.moves.len(), 81);
+    assert_eq!(game.sub_boards, Board::new(9));
+
+    // Joining an existing game, player_2 plays "X"
+    let info_2 = mock_info("player_2", &[]);
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_2, 0),
+        pubkey: None,
+    };
+
+    join_game(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+    reveal_seeds(&mut deps, &env, 0);
+
+    // Submitting a classic move to an ultimate game fails
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(1),
+        coordinates: None,
+    };
+    let res = submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::WrongVariant {});
+
+    // The first move can go anywhere and sends "O" to sub-board 8
+    let msg = SubmitUltimateMoveMsg {
+        game_id: 0,
+        sub_board: 4,
+        cell: 8,
+    };
+    let res = submit_ultimate_move(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "submit_ultimate_move"),
+            attr("game_id", "0"),
+            attr("sub_board", "4"),
+            attr("cell", "8"),
+            attr("role", "X"),
+            attr("next_sub_board", "8"),
+        ]
+    );
+
+    // Playing in any other sub-board fails
+    let msg = SubmitUltimateMoveMsg {
+        game_id: 0,
+        sub_board: 1,
+        cell: 2,
+    };
+    let res = submit_ultimate_move(deps.as_mut(), env.clone(), info_1.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::WrongSubBoard { expected: 8 }
+    );
+
+    // "X" takes sub-board 1 first, then 3
+    let moves = [
+        (&info_1, 8, 2),
+        (&info_2, 2, 5),
+        (&info_1, 5, 2),
+        (&info_2, 2, 7),
+        (&info_1, 7, 1),
+        (&info_2, 1, 1),
+        (&info_1, 1, 2),
+        (&info_2, 2, 4),
+        (&info_1, 4, 3),
+        (&info_2, 3, 5),
+        (&info_1, 5, 3),
+        (&info_2, 3, 8),
+        (&info_1, 8, 1),
+        (&info_2, 1, 9),
+        (&info_1, 9, 1),
+        (&info_2, 1, 5),
+        (&info_1, 5, 5),
+        (&info_2, 5, 4),
+        (&info_1, 4, 5),
+        (&info_2, 5, 1),
+    ];
+    for (info, sub_board, cell) in moves {
+        let msg = SubmitUltimateMoveMsg {
+            game_id: 0,
+            sub_board,
+            cell,
+        };
+        submit_ultimate_move(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    }
+
+    let game = load_game(&deps.storage, 0).unwrap();
+    assert_eq!(game.sub_boards.cell(0), Cell::X);
+    assert_eq!(game.status, GameStatus::InProgress);
+
+    // sent to the already won sub-board 1, "O" can play in any open sub-board but that one
+    assert_eq!(game.next_sub_board, None);
+
+    let msg = SubmitUltimateMoveMsg {
+        game_id: 0,
+        sub_board: 1,
+        cell: 3,
+    };
+    let res = submit_ultimate_move(deps.as_mut(), env.clone(), info_1.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::SubBoardClosed {});
+
+    let moves = [(&info_1, 8, 3), (&info_2, 3, 2), (&info_1, 2, 2)];
+    for (info, sub_board, cell) in moves {
+        let msg = SubmitUltimateMoveMsg {
+            game_id: 0,
+            sub_board,
+            cell,
+        };
+        submit_ultimate_move(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    }
+
+    // Winning sub-board 2 completes the top row of sub-boards
+    let msg = SubmitUltimateMoveMsg {
+        game_id: 0,
+        sub_board: 2,
+        cell: 1,
+    };
+    let res = submit_ultimate_move(deps.as_mut(), env, info_2, msg).unwrap();
+    assert!(res.attributes.contains(&attr("result", "x_wins")));
+
+    let game = load_game(&deps.storage, 0).unwrap();
+
+    // game completed & player_2 wins as "X"
+    assert_eq!(game.status, GameStatus::Completed);
+    assert_eq!(game.result, Some(GameResult::XWins));
+    assert_eq!(&game.sub_boards.cells()[0..3], [Cell::X, Cell::X, Cell::X]);
+    assert_eq!(game.winner(), Some(Addr::unchecked("player_2")));
+}
+
+#[test]
+fn winning_a_qubic_game() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Instantiating
+    let instantiate_msg = InstantiateMsg::default();
+    let admin_info = mock_info("instantiatoor", &[]);
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        admin_info.clone(),
+        instantiate_msg,
+    )
+    .unwrap();
+
+    // Creating a qubic game
+    let info_1 = mock_info("player_1", &[]);
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        variant: Some(GameVariant::Qubic),
+        ..Default::default()
+    };
+
+    create_game(deps.as_mut(), env.clone(), info_1.clone(), msg).unwrap();
+
+    let game = load_game(&deps.storage, 0).unwrap();
+    assert_eq!(game.moves.len(), 64);
+    assert_eq!((game.rows, game.cols, game.layers), (4, 4, 4));
+
+    // Joining an existing game, player_2 plays "X"
+    let info_2 = mock_info("player_2", &[]);
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_2, 0),
+        pubkey: None,
+    };
+
+    join_game(deps.as_mut(), env.clone(), info_2.clone(), msg).unwrap();
+    reveal_seeds(&mut deps, &env, 0);
+
+    // Submitting a move without a position or coordinates fails
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: None,
+        coordinates: None,
+    };
+    let res = submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg);
+    assert_eq!(res.unwrap_err(), ContractError::InvalidMove {});
+
+    // Submitting a move outside the cube fails
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: None,
+        coordinates: Some(Coordinates { x: 1, y: 1, z: 5 }),
+    };
+    let res = submit_move(deps.as_mut(), env.clone(), info_2.clone(), msg);
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InvalidPosition {
+            position: "(1, 1, 5)".to_string(),
+            max: 64,
+        }
+    );
+
+    // "X" plays along the space diagonal while "O" fills the first row of the first layer
+    let moves = [
+        (&info_2, 1, 1, 1),
+        (&info_1, 2, 1, 1),
+        (&info_2, 2, 2, 2),
+        (&info_1, 3, 1, 1),
+        (&info_2, 3, 3, 3),
+        (&info_1, 1, 4, 4),
+    ];
+    for (info, x, y, z) in moves {
+        let msg = SubmitMoveMsg {
+            game_id: 0,
+            position: None,
+            coordinates: Some(Coordinates { x, y, z }),
+        };
+        submit_move(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    }
+
+    let game = load_game(&deps.storage, 0).unwrap();
+    assert_eq!(game.status, GameStatus::InProgress);
+
+    // Completing the space diagonal through its last corner, addressed by position
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(64),
+        coordinates: None,
+    };
+    let res = submit_move(deps.as_mut(), env, info_2, msg).unwrap();
+    assert!(res.attributes.contains(&attr("result", "x_wins")));
+
+    let game = load_game(&deps.storage, 0).unwrap();
+
+    // game completed & player_2 wins as "X"
+    assert_eq!(game.status, GameStatus::Completed);
+    assert_eq!(game.result, Some(GameResult::XWins));
+    assert_eq!(game.winner(), Some(Addr::unchecked("player_2")));
+}
+
+#[test]
+fn checking_qubic_lines() {
+    // every straight line of four cells through the cube, found by walking each step from each cell
+    let mut lines = vec![];
+    for start in 0..64 {
+        let (z, y, x) = (start / 16, start / 4 % 4, start % 4);
+        for step in 0..27 {
+            let (dz, dy, dx) = (step / 9 - 1, step / 3 % 3 - 1, step % 3 - 1);
+            let cells: Vec<i32> = (0..4)
+                .map(|i| (z + dz * i, y + dy * i, x + dx * i))
+                .filter(|(z, y, x)| (0..4).contains(z) && (0..4).contains(y) && (0..4).contains(x))
+                .map(|(z, y, x)| z * 16 + y * 4 + x)
+                .collect();
+            if step != 13 && cells.len() == 4 {
+                let mut line = cells;
+                line.sort();
+                lines.push(line);
+            }
+        }
+    }
+    lines.sort();
+    lines.dedup();
+    assert_eq!(lines.len(), 76);
+
+    // each of them wins on its own
+    for line in lines {
+        let mut moves = Board::new(64);
+        for cell in line {
+            moves.place(cell as usize, Cell::O).unwrap();
+        }
+        assert_eq!(moves.winner(4, 4, 4, 4), Some(Cell::O));
+    }
+
+    // four cells that bend between layers don't
+    let mut moves = Board::new(64);
+    for cell in [0, 1, 2, 19] {
+        moves.place(cell, Cell::X).unwrap();
+    }
+    assert_eq!(moves.winner(4, 4, 4, 4), None);
+}
+
+// a board with the stored layout
+#[derive(Serialize, Deserialize, Debug)]
+struct PackedBoard(#[serde(with = "packed")] Board);
+
+#[test]
+fn playing_on_a_board() {
+    let mut board = Board::new(9);
+    assert_eq!(board.legal_moves().len(), 9);
+
+    // cells can only be taken once and must be on the board
+    board.place(4, Cell::X).unwrap();
+    assert_eq!(
+        board.place(4, Cell::O).unwrap_err(),
+        ContractError::PositionTaken {}
+    );
+    assert_eq!(
+        board.place(9, Cell::O).unwrap_err(),
+        ContractError::InvalidPosition {
+            position: "10".to_string(),
+            max: 9
+        }
+    );
+    assert!(!board.legal_moves().contains(&4));
+
+    // boards keep the list of "X", "O" and "-" strings on the wire
+    let json = to_binary(&board).unwrap();
+    assert_eq!(
+        json.to_vec(),
+        br#"["-","-","-","-","X","-","-","-","-"]"#.to_vec()
+    );
+    assert_eq!(from_binary::<Board>(&json).unwrap(), board);
+
+    // and are stored as the cell count followed by the X and O bitmasks
+    let json = to_binary(&PackedBoard(board.clone())).unwrap();
+    assert_eq!(json.to_vec(), b"[9,16,0]".to_vec());
+    assert_eq!(from_binary::<PackedBoard>(&json).unwrap().0, board);
+
+    // masks overlapping each other or running off the board are rejected
+    for packed in [&b"[9,16,16]"[..], b"[9,512,0]", b"[9,16]"] {
+        from_binary::<PackedBoard>(&Binary::from(packed)).unwrap_err();
+    }
+
+    // a full board without a line has no winner
+    let board: Board = "XOXXOOOXX"
+        .chars()
+        .map(|cell| cell.to_string().parse().unwrap())
+        .collect();
+    assert!(board.is_full());
+    assert!(board.legal_moves().is_empty());
+    assert_eq!(board.winner(3, 3, 1, 3), None);
+}
+
+// reference rules for 3x3 boards, written independently of the board engine
+fn reference_winner(cells: &[Cell]) -> Option<Cell> {
+    let mut lines = vec![[0, 4, 8], [2, 4, 6]];
+    for i in 0..3 {
+        lines.push([i * 3, i * 3 + 1, i * 3 + 2]);
+        lines.push([i, i + 3, i + 6]);
+    }
+
+    lines.into_iter().find_map(|[a, b, c]| {
+        let role = cells[a];
+        (role != Cell::Empty && cells[b] == role && cells[c] == role).then_some(role)
+    })
+}
+
+// every position reachable from the empty board, play stopping once someone wins
+fn legal_positions(cells: &mut Vec<Cell>, role: Cell, positions: &mut HashSet<Vec<Cell>>) {
+    if !positions.insert(cells.clone()) || reference_winner(cells).is_some() {
+        return;
+    }
+
+    for index in 0..9 {
+        if cells[index] == Cell::Empty {
+            cells[index] = role;
+            legal_positions(cells, role.opponent(), positions);
+            cells[index] = Cell::Empty;
+        }
+    }
+}
+
+#[test]
+fn checking_every_classic_position() {
+    let mut positions = HashSet::new();
+    legal_positions(&mut vec![Cell::Empty; 9], Cell::X, &mut positions);
+    assert_eq!(positions.len(), 5478);
+
+    let (mut x_wins, mut o_wins, mut draws) = (0, 0, 0);
+    for cells in positions {
+        let expected = reference_winner(&cells);
+        let board: Board = cells.iter().copied().collect();
+        assert_eq!(board.winner(3, 3, 1, 3), expected, "{cells:?}");
+
+        // the generic line scan agrees once the board is padded into a 4x4 one
+        let padded: Board = (0..16)
+            .map(|index| match (index / 4, index % 4) {
+                (row, col) if row < 3 && col < 3 => cells[row * 3 + col],
+                _ => Cell::Empty,
+            })
+            .collect();
+        assert_eq!(padded.winner(4, 4, 1, 3), expected, "{cells:?}");
+
+        match expected {
+            Some(Cell::X) => x_wins += 1,
+            Some(_) => o_wins += 1,
+            None if board.is_full() => draws += 1,
+            None => {}
+        }
+    }
+
+    // the well known counts of finished games
+    assert_eq!((x_wins, o_wins, draws), (626, 316, 16));
+}
+
+#[test]
+fn playing_random_games() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("instantiatoor", &[]),
+        InstantiateMsg::default(),
+    )
+    .unwrap();
+
+    // xorshift, seeded so failures can be replayed
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut random = |max: usize| {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed as usize % max
+    };
+
+    for game_id in 0..200 {
+        let variant = match game_id % 2 {
+            0 => GameVariant::Classic,
+            _ => GameVariant::Misere,
+        };
+        let info_1 = mock_info("player_1", &[]);
+        let msg = ExecuteMsg::CreateGame(CreateGameMsg {
+            commitment: commitment(&info_1, game_id),
+            variant: Some(variant.clone()),
+            ..Default::default()
+        });
+        execute(deps.as_mut(), env.clone(), info_1, msg).unwrap();
+
+        let info_2 = mock_info("player_2", &[]);
+        let msg = ExecuteMsg::JoinGame(JoinGameMsg {
+            game_id,
+            commitment: commitment(&info_2, game_id),
+            pubkey: None,
+        });
+        execute(deps.as_mut(), env.clone(), info_2, msg).unwrap();
+        reveal_seeds(&mut deps, &env, game_id);
+
+        let submit = |position: usize| {
+            ExecuteMsg::SubmitMove(SubmitMoveMsg {
+                game_id,
+                position: Some(position as u16 + 1),
+                coordinates: None,
+            })
+        };
+
+        let mut game = load_game(&deps.storage, game_id).unwrap();
+        while game.status == GameStatus::InProgress {
+            let player = game.next_turn.clone().unwrap();
+            let info = mock_info(player.as_str(), &[]);
+            let role = match game.players[0] == player {
+                true => Cell::X,
+                false => Cell::O,
+            };
+
+            // taken cells are refused without touching the board
+            let taken: Vec<usize> = (0..9)
+                .filter(|index| game.moves.cell(*index) != Cell::Empty)
+                .collect();
+            if !taken.is_empty() {
+                let position = taken[random(taken.len())];
+                let err = execute(deps.as_mut(), env.clone(), info.clone(), submit(position));
+                assert_eq!(err.unwrap_err(), ContractError::PositionTaken {});
+            }
+
+            // a legal move fills exactly that cell and hands the turn over
+            let legal_moves = game.moves.legal_moves();
+            let position = legal_moves[random(legal_moves.len())];
+            execute(deps.as_mut(), env.clone(), info, submit(position)).unwrap();
+
+            let before = game;
+            game = load_game(&deps.storage, game_id).unwrap();
+            assert_eq!(game.moves.cell(position), role);
+            assert_eq!(game.moves.legal_moves().len(), legal_moves.len() - 1);
+            for index in (0..9).filter(|index| *index != position) {
+                assert_eq!(game.moves.cell(index), before.moves.cell(index));
+            }
+            if game.status == GameStatus::InProgress {
+                assert_ne!(game.next_turn, Some(player));
+            }
+        }
+
+        // the result follows the reference rules, misère games going to whoever avoided the line
+        let line = reference_winner(&game.moves.cells());
+        let expected = match (&variant, line) {
+            (GameVariant::Misere, Some(loser)) => Some(loser.opponent()),
+            (_, line) => line,
+        };
+        let expected = match expected {
+            Some(Cell::X) => GameResult::XWins,
+            Some(_) => GameResult::OWins,
+            None => {
+                assert!(game.moves.is_full());
+                GameResult::Draw
+            }
+        };
+        assert_eq!(game.status, GameStatus::Completed);
+        assert_eq!(game.result, Some(expected));
+
+        // no moves are accepted once the game is completed
+        for player in game.players.iter() {
+            let info = mock_info(player.as_str(), &[]);
+            let err = execute(deps.as_mut(), env.clone(), info, submit(random(9)));
+            assert_eq!(err.unwrap_err(), ContractError::GameNotInProgress {});
+        }
+        assert_eq!(load_game(&deps.storage, game_id).unwrap(), game);
+    }
+}
+
+#[test]
+fn measuring_stored_game_size() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    instantiate(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("instantiatoor", &[]),
+        InstantiateMsg::default(),
+    )
+    .unwrap();
+
+    // Playing the first move of a classic game
+    let info_1 = mock_info("player_1", &[]);
+    let msg = CreateGameMsg {
+        commitment: commitment(&info_1, 0),
+        ..Default::default()
+    };
+    create_game(deps.as_mut(), env.clone(), info_1, msg).unwrap();
+
+    let info_2 = mock_info("player_2", &[]);
+    let msg = JoinGameMsg {
+        game_id: 0,
+        commitment: commitment(&info_2, 0),
+        pubkey: None,
+    };
+    join_game(deps.as_mut(), env.clone(), info_2, msg).unwrap();
+    reveal_seeds(&mut deps, &env, 0);
+
+    let game = load_game(&deps.storage, 0).unwrap();
+    let msg = SubmitMoveMsg {
+        game_id: 0,
+        position: Some(5),
+        coordinates: None,
+    };
+    let info = mock_info(game.next_turn.unwrap().as_str(), &[]);
+    submit_move(deps.as_mut(), env, info, msg).unwrap();
+
+    // The stored record against the same game with its boards as lists of "X", "O" and "-"
+    // strings, the layout queries return
+    let game = load_game(&deps.storage, 0).unwrap();
+    let bitboard = deps.storage.get(&games().key(0)).unwrap();
+    let strings = to_vec(&game).unwrap();
+
+    // a 3x3 board goes from 37 bytes down to 8, an empty one from 2 up to 3
+    assert_eq!(to_vec(&PackedBoard(game.moves.clone())).unwrap().len(), 8);
+    assert_eq!(to_vec(&game.moves).unwrap().len(), 37);
+    assert_eq!(strings.len() - bitboard.len(), 37 - 8 - 1);
+}
+
+#[test]
+fn migrating_a_legacy_store() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+
+    // Populating the store the way the unversioned contract did
+    let (x, o) = (Addr::unchecked("player_x"), Addr::unchecked("player_o"));
+    let board = |cells: &str| -> Vec<String> { cells.chars().map(|c| c.to_string()).collect() };
+    let legacy_games = [
+        (GameStatus::Open, "---------", None),
+        (GameStatus::InProgress, "X---O----", None),
+        (GameStatus::Completed, "XXXOO----", Some(x.clone())),
+        (GameStatus::Completed, "X-O------", Some(x.clone())),
+        (GameStatus::Completed, "XOXXOOOXX", None),
+    ];
+
+    LEGACY_CONFIG
+        .save(
+            &mut deps.storage,
+            &LegacyConfig {
+                owner: Addr::unchecked("instantiatoor"),
+            },
+        )
+        .unwrap();
+    STATE
+        .save(&mut deps.storage, &State { latest_game_id: 7 })
+        .unwrap();
+    for (id, (status, moves, winner)) in legacy_games.into_iter().enumerate() {
+        let players = match status {
+            GameStatus::Open => vec![o.clone()],
+            _ => vec![x.clone(), o.clone()],
+        };
+        let legacy_game = LegacyGame {
+            id: id as u64,
+            players,
+            status,
+            moves: board(moves),
+            next_turn: None,
+            winner,
+        };
+        LEGACY_GAMES
+            .save(&mut deps.storage, id as u64, &legacy_game)
+            .unwrap();
+    }
+
+    // self-joins were allowed back then
+    let legacy_game = LegacyGame {
+        id: 5,
+        players: vec![x.clone(), x.clone()],
+        status: GameStatus::Completed,
+        moves: board("X-O------"),
+        next_turn: None,
+        winner: Some(x.clone()),
+    };
+    LEGACY_GAMES
+        .save(&mut deps.storage, 5, &legacy_game)
+        .unwrap();
+    let legacy_game = LegacyGame {
+        id: 6,
+        status: GameStatus::InProgress,
+        moves: board("X---O----"),
+        next_turn: Some(x.clone()),
+        winner: None,
+        ..legacy_game
+    };
+    LEGACY_GAMES
+        .save(&mut deps.storage, 6, &legacy_game)
+        .unwrap();
+
+    // Migrating in batches
+    let res = migrate(deps.as_mut(), env.clone(), MigrateMsg { limit: Some(4) }).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "migrate"),
+            attr("version", env!("CARGO_PKG_VERSION")),
+            attr("migrated_games", "4"),
+            attr("legacy_games_pending", "true"),
+        ]
+    );
+
+    let res = migrate(deps.as_mut(), env.clone(), MigrateMsg { limit: None }).unwrap();
+    assert!(res.attributes.contains(&attr("migrated_games", "3")));
+    assert!(res
+        .attributes
+        .contains(&attr("legacy_games_pending", "false")));
+
+    // config gets the new settings' defaults & the contract is versioned
+    let msg = QueryMsg::Config {};
+    let res: ConfigResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(res.owner, Some(Addr::unchecked("instantiatoor")));
+    assert_eq!(res.initial_rating, 1200);
+
+    let version = get_contract_version(&deps.storage).unwrap();
+    assert_eq!(version.contract, "crates.io:tic-tac-toe");
+
+    // games keep their board and players, results are derived from the legacy winner
+    let results: Vec<(GameStatus, Option<GameResult>)> = (0..7)
+        .map(|id| load_game(&deps.storage, id).unwrap())
+        .map(|game| (game.status, game.result))
+        .collect();
+    assert_eq!(
+        results,
+        vec![
+            (GameStatus::Cancelled, Some(GameResult::Cancelled)),
+            (GameStatus::InProgress, None),
+            (GameStatus::Completed, Some(GameResult::XWins)),
+            (
+                GameStatus::Completed,
+                Some(GameResult::Unverified { winner: x.clone() })
+            ),
+            (GameStatus::Completed, Some(GameResult::Draw)),
+            (
+                GameStatus::Completed,
+                Some(GameResult::Unverified { winner: x.clone() })
+            ),
+            (GameStatus::Cancelled, Some(GameResult::Cancelled)),
+        ]
+    );
+
+    // a self-joined game in progress is cancelled, so it can't be resigned
+    let msg = ResignMsg { game_id: 6 };
+    let res = resign(deps.as_mut(), env.clone(), mock_info("player_x", &[]), msg);
+    assert_eq!(res.unwrap_err(), ContractError::GameNotInProgress {});
+
+    let game = load_game(&deps.storage, 1).unwrap();
+    assert_eq!(
+        game.moves,
+        board("X---O----")
+            .iter()
+            .map(|cell| cell.parse().unwrap())
+            .collect()
+    );
+    assert_eq!((game.rows, game.cols, game.win_length), (3, 3, 3));
+    assert_eq!(game.players, vec![x.clone(), o.clone()]);
+
+    // migrated games are indexed by status and player
+    let msg = QueryMsg::ListGames {
+        status: Some(GameStatus::Completed),
+        start_after: None,
+        limit: None,
+    };
+    let res: ListGamesResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(res.games.len(), 4);
+
+    let msg = QueryMsg::GamesByPlayer {
+        player: o.to_string(),
+        start_after: None,
+        limit: None,
+    };
+    let res: ListGamesResponse =
+        from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+    assert_eq!(res.games.len(), 5);
+
+    // Migrating a versioned store again leaves games untouched
+    let res = migrate(deps.as_mut(), env.clone(), MigrateMsg { limit: None }).unwrap();
+    assert!(res.attributes.contains(&attr("migrated_games", "0")));
+
+    // Migrating from another contract or a newer version fails
+    for (contract, version) in [
+        ("crates.io:other", "0.1.0"),
+        ("crates.io:tic-tac-toe", "99.0.0"),
+    ] {
+        set_contract_version(&mut deps.storage, contract, version).unwrap();
+
+        let res = migrate(deps.as_mut(), env.clone(), MigrateMsg { limit: None });
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::CannotMigrate {
+                contract: contract.to_string(),
+                version: version.to_string(),
+            }
+        );
+    }
 }